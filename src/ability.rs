@@ -1,50 +1,52 @@
-use std::{collections::HashMap, fs::File, path::Path, sync::Mutex};
+use std::{
+    collections::HashMap,
+    sync::{Mutex, OnceLock},
+};
 
-use colored::Colorize;
 use lazy_static::lazy_static;
 use nohash_hasher::BuildNoHashHasher;
 use regex::Regex;
-use serde::{de::IntoDeserializer, Deserialize, Deserializer};
-use serde_repr::Deserialize_repr;
-// use simd_json::from_reader;
-use serde_json::from_reader;
+use serde::{
+    de::{Error, IntoDeserializer},
+    Deserialize, Deserializer, Serialize, Serializer,
+};
+use serde_repr::{Deserialize_repr, Serialize_repr};
 use tinyvec::ArrayVec;
 
 use crate::{
-    battle::BattleData,
+    assets::{self, AssetError},
+    battle::{BattleData, BattleEvent},
     game::RoundWin,
-    modifiers::{EventTime, Modifier},
+    modifiers::{EventTime, Keyword, Modifier},
     types::Clan,
 };
 
-pub static mut PRINT: bool = true;
-macro_rules! println {
-    ($($rest:tt)*) => {
-        unsafe {
-            if PRINT {
-                std::println!($($rest)*)
-            }
-        }
+pub type Abilities = HashMap<u32, Ability, BuildNoHashHasher<u32>>;
+
+static LOADED_ABILITIES: OnceLock<Abilities> = OnceLock::new();
+
+/// Reads the pre-compiled abilities from `compiled.json` in the asset
+/// directory. Abilities are only read once; later calls return the same map.
+pub fn load_abilities() -> Result<&'static Abilities, AssetError> {
+    if let Some(abilities) = LOADED_ABILITIES.get() {
+        return Ok(abilities);
     }
+
+    let bytes = assets::read(assets::ABILITIES_FILE)?;
+    let abilities: Abilities = serde_json::from_slice(&bytes)
+        .map_err(|e| AssetError::invalid(assets::ABILITIES_FILE, e))?;
+    Ok(LOADED_ABILITIES.get_or_init(|| abilities))
 }
 
 lazy_static! {
-    // pub static ref ABILITIES: HashMap<u32, Cow<'static, Ability>, BuildNoHashHasher<u32>> = {
-    pub static ref ABILITIES: HashMap<u32, Ability, BuildNoHashHasher<u32>> = {
-        let data_file =
-            File::open(Path::new("./assets/compiled.json")).unwrap();
-        from_reader(data_file).expect("Error while reading JSON file")
-        // from_reader::<_, HashMap<u32, Ability>>(data_file)
-        //     .expect("Error while reading JSON file")
-        //     .into_iter()
-        //     .map(|(k, v)| (k, Cow::Owned(v)))
-        //     .collect()
-    };
+    pub static ref ABILITIES: &'static Abilities =
+        load_abilities().unwrap_or_else(|e| panic!("{}", e));
     pub static ref CONDITION_CLANS: Mutex<HashMap<u8, Vec<Clan>>> = Mutex::new(HashMap::new());
     pub static ref CLANS_REGEX: Regex = Regex::new(r"\[[Cc]lan:(\d+)\]").unwrap();
+    static ref BET_REGEX: Regex = Regex::new(r"^Bet > (\d+) Pillz$").unwrap();
 }
 
-#[derive(Clone, Copy, Debug, Deserialize_repr, PartialEq)]
+#[derive(Clone, Copy, Debug, Serialize_repr, Deserialize_repr, PartialEq, Hash)]
 #[repr(usize)]
 pub enum AbilityType {
     Global = 1,
@@ -54,7 +56,7 @@ pub enum AbilityType {
     GlobalBonus = 5,
 }
 
-#[derive(Clone, Copy, Debug, Deserialize, PartialEq)]
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq, Hash)]
 pub struct Ability {
     // pub ability: String,
     pub ability_type: AbilityType,
@@ -91,12 +93,25 @@ impl Ability {
             return false;
         }
 
-        for cond in self.conditions.iter() {
-            if !cond.as_ref().unwrap().is_met(data) {
-                println!("{}: {:?}", "Condition not met".red(), cond);
+        // Remove Ability Conditions
+        let conditions = if data.card.borrow().keyword(Keyword::RemoveConditions) {
+            &[][..]
+        } else {
+            &self.conditions[..]
+        };
+        for cond in conditions.iter() {
+            let condition = cond.unwrap();
+            if !condition.is_met(data) {
+                data.log(BattleEvent::ConditionNotMet {
+                    player: data.player_type(),
+                    condition,
+                });
                 return false;
             }
-            println!("{}: {:?}", "Condition met".green(), cond);
+            data.log(BattleEvent::ConditionMet {
+                player: data.player_type(),
+                condition,
+            });
         }
 
         match self.ability_type {
@@ -104,14 +119,13 @@ impl Ability {
                 !data.card.borrow().ability.is_blocked()
             }
             AbilityType::Bonus | AbilityType::GlobalBonus => !data.card.borrow().bonus.is_blocked(),
-            _ => true,
+            AbilityType::Global => !data.card.borrow().is_leader_blocked(),
         }
     }
     pub fn apply(&mut self, data: &BattleData) -> Option<Ability> {
         let mut ability: Option<Ability> = None;
         if self.can_apply(data) {
             for modifier in self.modifiers.iter_mut() {
-                println!("{}: {:?}", "Applying".yellow(), modifier.as_ref().unwrap());
                 ability = modifier.as_mut().unwrap().apply(data);
             }
         }
@@ -119,7 +133,7 @@ impl Ability {
     }
 }
 
-#[derive(Debug, Clone, Copy, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Hash)]
 #[serde(remote = "Condition")]
 pub enum Condition {
     Courage,
@@ -135,6 +149,7 @@ pub enum Condition {
     Team,
     Symmetry,
     Revenge,
+    #[serde(alias = "Repris")]
     Reprisal,
     Day,
     Night,
@@ -144,9 +159,13 @@ pub enum Condition {
     Reanimate,
     Stop,
     // StopBonus,
+    Unison,
+    Disunion,
+    Perfect,
+    /// More than this many pillz, fury included, were bet this round.
+    Bet(u8),
     Infiltrate(u8),
     Versus(u8),
-    #[serde(other)]
     None,
 }
 
@@ -156,23 +175,74 @@ impl<'de> Deserialize<'de> for Condition {
         D: Deserializer<'de>,
     {
         let s = String::deserialize(deserializer)?;
-        if s.ends_with("]") {
-            let clans = CLANS_REGEX
-                .captures_iter(s.as_str())
-                .map(|m| Clan::from(*&m[1].parse::<u8>().unwrap()))
-                .collect::<Vec<Clan>>();
-
-            let mut cache = CONDITION_CLANS.lock().unwrap();
-            let key = cache.len() as u8;
-            cache.insert(key, clans);
-
-            if s.starts_with("Versus") {
-                Ok(Condition::Versus(key))
-            } else {
-                Ok(Condition::Infiltrate(key))
+        Condition::from_name(&s)
+            .ok_or_else(|| D::Error::custom(format!("unknown condition {:?}", s)))
+    }
+}
+
+impl Condition {
+    /// Parses a condition name as written in `compiled.json`, or `None` if
+    /// the name isn't a known condition.
+    pub fn from_name(name: &str) -> Option<Condition> {
+        if name.ends_with("]") {
+            return Some(Condition::from_clan_tags(name));
+        }
+        if let Some(caps) = BET_REGEX.captures(name) {
+            return caps[1].parse().ok().map(Condition::Bet);
+        }
+        let name: serde::de::value::StrDeserializer<serde::de::value::Error> =
+            name.into_deserializer();
+        Condition::deserialize(name).ok()
+    }
+
+    /// `[Clan:N]...` is an Infiltrate condition, `Versus [Clan:N]...` a
+    /// Versus condition.
+    fn from_clan_tags(s: &str) -> Condition {
+        let clans = CLANS_REGEX
+            .captures_iter(s)
+            .map(|m| Clan::from(*&m[1].parse::<u8>().unwrap()))
+            .collect::<Vec<Clan>>();
+
+        // Reuse the key if the same clans were already seen, so loading
+        // snapshots doesn't grow the cache
+        let mut cache = CONDITION_CLANS.lock().unwrap();
+        let key = match cache.iter().find(|(_, c)| **c == clans) {
+            Some((&key, _)) => key,
+            None => {
+                let key = cache.len() as u8;
+                cache.insert(key, clans);
+                key
             }
+        };
+
+        if s.starts_with("Versus") {
+            Condition::Versus(key)
         } else {
-            Condition::deserialize(s.into_deserializer())
+            Condition::Infiltrate(key)
+        }
+    }
+}
+
+/// Writes clan conditions back in the `[Clan:N]` form they were read from.
+impl Serialize for Condition {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        match *self {
+            Condition::Infiltrate(key) | Condition::Versus(key) => {
+                let clans = CONDITION_CLANS.lock().unwrap()[&key]
+                    .iter()
+                    .map(|&clan| format!("[Clan:{}]", clan as u8))
+                    .collect::<String>();
+                if let Condition::Versus(_) = self {
+                    serializer.serialize_str(&format!("Versus {}", clans))
+                } else {
+                    serializer.serialize_str(&clans)
+                }
+            }
+            Condition::Bet(n) => serializer.serialize_str(&format!("Bet > {} Pillz", n)),
+            _ => Condition::serialize(self, serializer),
         }
     }
 }
@@ -182,9 +252,8 @@ impl Condition {
     pub fn is_met(&self, data: &BattleData) -> bool {
         match self {
             Condition::Defeat => data.player.borrow().won == RoundWin::LOSE,
-            // Condition::Night => round.day == false,
-            // Condition::Day => round.day == true,
-            Condition::Night | Condition::Day => true,
+            Condition::Night => !data.day,
+            Condition::Day => data.day,
             Condition::Courage => data.first,
             Condition::Revenge => data.player.borrow().won_previous == RoundWin::LOSE,
             Condition::Confidence => data.player.borrow().won_previous == RoundWin::WIN,
@@ -199,9 +268,19 @@ impl Condition {
             Condition::Stop => data.card.borrow().ability.cancelled != 0,
             Condition::Symmetry => data.card.borrow().index == data.opp_card.borrow().index,
             Condition::Asymmetry => data.card.borrow().index != data.opp_card.borrow().index,
+            Condition::Unison => data.card.borrow().clan() == data.opp_card.borrow().clan(),
+            Condition::Disunion => data.card.borrow().clan() != data.opp_card.borrow().clan(),
+            // Every card played before this one won its round
+            Condition::Perfect => {
+                data.round > 0
+                    && data.hand.cards.iter().all(|card| {
+                        let card = card.borrow();
+                        !card.played || card.won
+                    })
+            }
+            Condition::Bet(n) => data.player_pillz_used > *n,
             Condition::Infiltrate(key) => {
                 let hand = data.hand;
-                println!("{}", hand.oculus_clan);
                 if hand.oculus_clan == Clan::None {
                     false
                 } else {
@@ -224,3 +303,142 @@ impl Condition {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+
+    use super::{Condition, ABILITIES};
+    use crate::{
+        battle::{BattleData, BattleLog, Events},
+        card::{load_cards, Hand},
+        game::{Game, Player, PlayerType},
+        output::Silent,
+    };
+
+    /// Runs `f` in the first round with Danae, the player's second card,
+    /// against `opp`.
+    fn with_data(opp: &str, f: impl FnOnce(BattleData)) {
+        let mut h1 = Hand::from_names("Elvis", "Danae", "Cell", "Figaro");
+        let mut h2 = Hand::from_names(opp, "Elvis", "Vivian", "Figaro");
+        let mut p1 = Player::new(PlayerType::Player);
+        let mut p2 = Player::new(PlayerType::Opponent);
+        let mut events = Events::default();
+
+        let h1 = h1.to_handcell();
+        let h2 = h2.to_handcell();
+        let p1 = RefCell::new(&mut p1);
+        let p2 = RefCell::new(&mut p2);
+        let events = RefCell::new(&mut events);
+        let log = RefCell::new(BattleLog::new(false));
+        f(BattleData {
+            round: 0,
+            day: true,
+            first: true,
+            player: &p1,
+            hand: &h1,
+            card: &h1.cards[1],
+            player_pillz_used: 0,
+            opp: &p2,
+            opp_hand: &h2,
+            opp_card: &h2.cards[0],
+            opp_pillz_used: 0,
+            events: &events,
+            log: &log,
+        });
+    }
+
+    #[test]
+    fn parses_conditions() {
+        assert_eq!(Condition::from_name("Unison"), Some(Condition::Unison));
+        assert_eq!(Condition::from_name("Repris"), Some(Condition::Reprisal));
+        assert_eq!(
+            Condition::from_name("Bet > 3 Pillz"),
+            Some(Condition::Bet(3))
+        );
+        assert_eq!(Condition::from_name("Impose Reprisal"), None);
+
+        let bet = serde_json::to_string(&Condition::Bet(5)).unwrap();
+        assert_eq!(bet, "\"Bet > 5 Pillz\"");
+        assert_eq!(
+            serde_json::from_str::<Condition>(&bet).unwrap(),
+            Condition::Bet(5)
+        );
+        assert!(serde_json::from_str::<Condition>("\"Unknown\"").is_err());
+    }
+
+    #[test]
+    fn every_card_ability_is_executable() {
+        for card in load_cards().unwrap() {
+            for (id, text) in [
+                (card.ability_id, &card.ability),
+                (card.bonus_id, &card.bonus),
+            ] {
+                if id == 0 {
+                    continue;
+                }
+                let ability = ABILITIES
+                    .get(&id)
+                    .unwrap_or_else(|| panic!("{}: #{} {:?} isn't compiled", card.name, id, text));
+                assert!(
+                    !ability.modifiers.is_empty(),
+                    "{}: #{} {:?} has no modifiers",
+                    card.name,
+                    id,
+                    text
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn unison_compares_clans() {
+        // Danae and Dr Falkenstein are both Skeelz, Cell is Nightmare
+        with_data("Dr Falkenstein", |data| {
+            assert!(Condition::Unison.is_met(&data));
+            assert!(!Condition::Disunion.is_met(&data));
+        });
+        with_data("Cell", |data| {
+            assert!(!Condition::Unison.is_met(&data));
+            assert!(Condition::Disunion.is_met(&data));
+        });
+    }
+
+    #[test]
+    fn perfect_counts_earlier_rounds() {
+        // Nothing was played before the first round
+        with_data("Cell", |data| assert!(!Condition::Perfect.is_met(&data)));
+        for (won, perfect) in [(true, true), (false, false)] {
+            with_data("Cell", |mut data| {
+                data.round = 1;
+                {
+                    let mut first = data.hand.cards[0].borrow_mut();
+                    (first.played, first.won) = (true, won);
+                }
+                assert_eq!(Condition::Perfect.is_met(&data), perfect);
+            });
+        }
+    }
+
+    #[test]
+    fn bet_counts_fury() {
+        for (used, met) in [(3, false), (4, true)] {
+            with_data("Cell", |mut data| {
+                data.player_pillz_used = used;
+                assert_eq!(Condition::Bet(3).is_met(&data), met);
+            });
+        }
+
+        // Todor's "Bet > 5 Pillz: -3 Opp Power, Min 0" against Vivian,
+        // with 2 or 3 pillz and fury
+        for (pillz, power) in [(2, 7), (3, 4)] {
+            let h1 = Hand::from_names("Todor", "Danae", "Cell", "Figaro");
+            let h2 = Hand::from_names("Vivian", "Hollow Spyke", "Cell", "Figaro");
+            let mut game = Game::new(h1, h2);
+            game.output = &Silent;
+            game.select(0, pillz, true);
+            game.select(0, 0, false);
+            assert_eq!(game.h2[0].power.value, power, "{} pillz", pillz);
+        }
+    }
+}
@@ -0,0 +1,140 @@
+use std::{
+    env,
+    fmt::{Display, Formatter},
+    fs, io,
+    path::{Path, PathBuf},
+    sync::OnceLock,
+};
+
+/// Environment variable naming the asset directory.
+pub const ASSETS_ENV: &str = "URBAN_ASSETS";
+const DEFAULT_DIR: &str = "./assets";
+
+/// Card data, exported from the TypeScript version.
+pub const CARDS_FILE: &str = "data.json";
+/// Pre-compiled abilities, keyed by ability id.
+pub const ABILITIES_FILE: &str = "compiled.json";
+/// The ability grammar used to compile `compiled.json`.
+pub const GRAMMAR_FILE: &str = "abilityTree.json";
+
+static DIR: OnceLock<PathBuf> = OnceLock::new();
+
+#[derive(Debug)]
+pub enum AssetError {
+    /// The file couldn't be read, and no copy was embedded.
+    Missing { path: PathBuf, source: io::Error },
+    /// The file was read but isn't valid data.
+    Invalid { path: PathBuf, message: String },
+}
+
+impl AssetError {
+    pub fn invalid(file: &str, error: impl Display) -> Self {
+        AssetError::Invalid {
+            path: path(file),
+            message: error.to_string(),
+        }
+    }
+}
+
+impl Display for AssetError {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        match self {
+            AssetError::Missing { path, source } => write!(
+                f,
+                "Can't read {}: {} (set the asset directory with --assets or ${})",
+                path.display(),
+                source,
+                ASSETS_ENV
+            ),
+            AssetError::Invalid { path, message } => {
+                write!(f, "Invalid asset {}: {}", path.display(), message)
+            }
+        }
+    }
+}
+
+impl std::error::Error for AssetError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            AssetError::Missing { source, .. } => Some(source),
+            AssetError::Invalid { .. } => None,
+        }
+    }
+}
+
+/// Sets the asset directory. Must be called before any card or ability is
+/// loaded; returns `false` if the directory was already decided.
+pub fn set_dir(dir: impl Into<PathBuf>) -> bool {
+    DIR.set(dir.into()).is_ok()
+}
+
+/// The asset directory: the one passed to `set_dir`, else `$URBAN_ASSETS`,
+/// else `./assets`.
+pub fn dir() -> &'static Path {
+    DIR.get_or_init(|| {
+        env::var_os(ASSETS_ENV)
+            .map(PathBuf::from)
+            .unwrap_or_else(|| PathBuf::from(DEFAULT_DIR))
+    })
+}
+
+#[inline]
+pub fn path(file: &str) -> PathBuf {
+    dir().join(file)
+}
+
+/// Reads a file from the asset directory. With the `embedded` feature, a
+/// file missing from the directory falls back to the copy built into the
+/// binary.
+pub fn read(file: &str) -> Result<Vec<u8>, AssetError> {
+    let path = path(file);
+    match fs::read(&path) {
+        Ok(bytes) => Ok(bytes),
+        Err(source) => match embedded(file) {
+            Some(bytes) if source.kind() == io::ErrorKind::NotFound => Ok(bytes.to_vec()),
+            _ => Err(AssetError::Missing { path, source }),
+        },
+    }
+}
+
+/// Loads the cards and abilities now instead of on first use, so a missing
+/// or broken asset surfaces as an error rather than a panic mid-game.
+pub fn load() -> Result<(), AssetError> {
+    crate::card::load_cards()?;
+    crate::ability::load_abilities()?;
+    Ok(())
+}
+
+#[cfg(feature = "embedded")]
+fn embedded(file: &str) -> Option<&'static [u8]> {
+    match file {
+        CARDS_FILE => Some(include_bytes!("../assets/data.json")),
+        ABILITIES_FILE => Some(include_bytes!("../assets/compiled.json")),
+        GRAMMAR_FILE => Some(include_bytes!("../assets/abilityTree.json")),
+        _ => None,
+    }
+}
+
+#[cfg(not(feature = "embedded"))]
+#[inline]
+fn embedded(_file: &str) -> Option<&'static [u8]> {
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{load, read, AssetError};
+
+    #[test]
+    fn loads_assets() {
+        load().unwrap();
+    }
+
+    #[test]
+    fn missing_file_is_an_error() {
+        match read("missing.json") {
+            Err(AssetError::Missing { path, .. }) => assert!(path.ends_with("missing.json")),
+            other => panic!("expected a missing asset, got {:?}", other),
+        }
+    }
+}
@@ -1,27 +1,452 @@
-use std::cell::RefCell;
+use std::{
+    cell::RefCell,
+    fmt::{Display, Formatter, Result},
+    slice::Iter,
+};
 
 use colored::Colorize;
+use serde::{Deserialize, Serialize};
 
 use crate::{
-    ability::{Ability, AbilityType},
+    ability::{Ability, AbilityType, Condition},
     card::{Card, HandCell},
-    game::Player,
-    modifiers::{EventTime, Modifier},
+    game::{Player, PlayerType, RoundWin},
+    modifiers::{Cancel, Copy, EventTime, Exchange, Keyword, Modifier, Protect, Stat},
     utils::StackVec4,
 };
 
-pub static mut PRINT: bool = true;
-macro_rules! println {
-    ($($rest:tt)*) => {
-        unsafe {
-            if PRINT {
-                std::println!($($rest)*)
+/// A single thing that happened during a battle. `player` is always the
+/// owner of the ability or card the entry is about.
+#[derive(Debug, Clone, PartialEq)]
+pub enum BattleEvent {
+    /// An ability with no modifiers the engine can execute was skipped.
+    AbilityDropped {
+        player: PlayerType,
+        ability_id: u32,
+    },
+    ConditionMet {
+        player: PlayerType,
+        condition: Condition,
+    },
+    ConditionNotMet {
+        player: PlayerType,
+        condition: Condition,
+    },
+    ModifierApplied {
+        player: PlayerType,
+        stat: Stat,
+        opp: bool,
+        before: u16,
+        after: u16,
+    },
+    /// A modifier was blocked by a cancel, a protection or a dead player.
+    ModifierBlocked {
+        player: PlayerType,
+        stat: Stat,
+        opp: bool,
+    },
+    Cancelled {
+        player: PlayerType,
+        cancel: Cancel,
+    },
+    CancelUndone {
+        player: PlayerType,
+        cancel: Cancel,
+    },
+    Protected {
+        player: PlayerType,
+        protect: Protect,
+        both: bool,
+    },
+    Copied {
+        player: PlayerType,
+        copy: Copy,
+    },
+    Exchanged {
+        player: PlayerType,
+        exchange: Exchange,
+    },
+    PillzRecovered {
+        player: PlayerType,
+        amount: u8,
+    },
+    Attack {
+        player: PlayerType,
+        attack: u16,
+    },
+    RoundWon {
+        player: PlayerType,
+    },
+    /// `player` lost `amount` life from the winning card's damage.
+    Damage {
+        player: PlayerType,
+        amount: u8,
+    },
+    LeaderCancelled {
+        player: PlayerType,
+        opp: bool,
+    },
+    TunedOut {
+        player: PlayerType,
+        both: bool,
+    },
+    /// A card was given a keyword for the round.
+    Keyword {
+        player: PlayerType,
+        keyword: Keyword,
+    },
+    StatusAdded {
+        player: PlayerType,
+        kind: StatusKind,
+        opp: bool,
+    },
+    /// A status effect on `player` ticked at the end of the round. `before`
+    /// and `after` are the life or pillz it changes.
+    StatusTicked {
+        player: PlayerType,
+        kind: StatusKind,
+        before: u8,
+        after: u8,
+    },
+}
+
+impl Display for BattleEvent {
+    fn fmt(&self, f: &mut Formatter) -> Result {
+        match self {
+            BattleEvent::AbilityDropped { player, ability_id } => write!(
+                f,
+                "{:?}: {} #{}",
+                player,
+                "Failed to add ability".red(),
+                ability_id
+            ),
+            BattleEvent::ConditionMet { player, condition } => {
+                write!(
+                    f,
+                    "{:?}: {}: {:?}",
+                    player,
+                    "Condition met".green(),
+                    condition
+                )
+            }
+            BattleEvent::ConditionNotMet { player, condition } => write!(
+                f,
+                "{:?}: {}: {:?}",
+                player,
+                "Condition not met".red(),
+                condition
+            ),
+            BattleEvent::ModifierApplied {
+                player,
+                stat,
+                opp,
+                before,
+                after,
+            } => write!(
+                f,
+                "{:?}: {} {}{:?} {} => {}",
+                player,
+                "Applying".yellow(),
+                if *opp { "Opp " } else { "" },
+                stat,
+                before,
+                after
+            ),
+            BattleEvent::ModifierBlocked { player, stat, opp } => write!(
+                f,
+                "{:?}: {} {}{:?}",
+                player,
+                "Can't apply".red(),
+                if *opp { "Opp " } else { "" },
+                stat
+            ),
+            BattleEvent::Cancelled { player, cancel } => {
+                write!(f, "{:?}: {} {:?}", player, "Cancel Opp".yellow(), cancel)
+            }
+            BattleEvent::CancelUndone { player, cancel } => {
+                write!(f, "{:?}: {} {:?}", player, "Undoing cancel".red(), cancel)
+            }
+            BattleEvent::Protected {
+                player,
+                protect,
+                both,
+            } => write!(
+                f,
+                "{:?}: {} {:?}{}",
+                player,
+                "Protection".yellow(),
+                protect,
+                if *both { " (both)" } else { "" }
+            ),
+            BattleEvent::Keyword { player, keyword } => {
+                write!(f, "{:?}: {} {:?}", player, "Keyword".yellow(), keyword)
+            }
+            BattleEvent::Copied { player, copy } => {
+                write!(f, "{:?}: {} {:?}", player, "Copy".yellow(), copy)
+            }
+            BattleEvent::Exchanged { player, exchange } => {
+                write!(f, "{:?}: {} {:?}", player, "Exchange".yellow(), exchange)
+            }
+            BattleEvent::PillzRecovered { player, amount } => {
+                write!(f, "{:?}: {} {}", player, "Recovered pillz".blue(), amount)
+            }
+            BattleEvent::Attack { player, attack } => {
+                write!(f, "{:?}: {} {}", player, "Attack".bright_red(), attack)
+            }
+            BattleEvent::RoundWon { player } => {
+                write!(f, "{:?}: {}", player, "Won the round".magenta())
+            }
+            BattleEvent::Damage { player, amount } => {
+                write!(f, "{:?}: {} {}", player, "Lost life".red(), amount)
+            }
+            BattleEvent::LeaderCancelled { player, opp } => write!(
+                f,
+                "{:?}: {} {}Leader",
+                player,
+                "Cancel".yellow(),
+                if *opp { "Opp " } else { "" }
+            ),
+            BattleEvent::TunedOut { player, both } => write!(
+                f,
+                "{:?}: {}{}",
+                player,
+                "Tune Out".yellow(),
+                if *both { " (both)" } else { "" }
+            ),
+            BattleEvent::StatusAdded { player, kind, opp } => write!(
+                f,
+                "{:?}: {} {}{:?}",
+                player,
+                "Status".yellow(),
+                if *opp { "Opp " } else { "" },
+                kind
+            ),
+            BattleEvent::StatusTicked {
+                player,
+                kind,
+                before,
+                after,
+            } => write!(
+                f,
+                "{:?}: {} {} => {}",
+                player,
+                format!("{:?}", kind).bright_magenta(),
+                before,
+                after
+            ),
+        }
+    }
+}
+
+/// Ordered record of everything that happened during one battle.
+///
+/// A disabled log drops every entry, so searches don't pay for allocations.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct BattleLog {
+    enabled: bool,
+    entries: Vec<BattleEvent>,
+}
+
+impl BattleLog {
+    pub fn new(enabled: bool) -> Self {
+        BattleLog {
+            enabled,
+            entries: Vec::new(),
+        }
+    }
+    #[inline]
+    pub fn push(&mut self, event: BattleEvent) {
+        if self.enabled {
+            self.entries.push(event);
+        }
+    }
+    #[inline]
+    pub fn entries(&self) -> &[BattleEvent] {
+        &self.entries
+    }
+    #[inline]
+    pub fn iter(&self) -> Iter<BattleEvent> {
+        self.entries.iter()
+    }
+}
+
+impl Display for BattleLog {
+    fn fmt(&self, f: &mut Formatter) -> Result {
+        for entry in self.entries.iter() {
+            writeln!(f, "{}", entry)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Hash)]
+pub enum StatusKind {
+    /// The player loses life at the end of every round, one more step each
+    /// round: `Corrosion 1` takes 1, then 2, then 3.
+    Corrosion,
+    /// The player loses pillz at the end of every round.
+    Corrupt,
+    /// The player gains life at the end of every round they lost.
+    Repair,
+    /// The player comes back with some life the first time a round ends
+    /// with them at 0.
+    Rebirth,
+}
+
+impl StatusKind {
+    /// Rounds the effect ticks for, `None` for the rest of the game.
+    pub fn duration(self) -> Option<u8> {
+        match self {
+            StatusKind::Corrosion
+            | StatusKind::Corrupt
+            | StatusKind::Repair
+            | StatusKind::Rebirth => None,
+        }
+    }
+
+    /// Whether a second effect of this kind adds to the first. Otherwise it
+    /// replaces it and starts over.
+    pub fn stacks(self) -> bool {
+        match self {
+            StatusKind::Corrupt => true,
+            StatusKind::Corrosion | StatusKind::Repair | StatusKind::Rebirth => false,
+        }
+    }
+
+    pub fn stat(self) -> Stat {
+        match self {
+            StatusKind::Corrupt => Stat::Pillz,
+            StatusKind::Corrosion | StatusKind::Repair | StatusKind::Rebirth => Stat::Life,
+        }
+    }
+
+    /// Whether the effect takes from the player rather than gives.
+    pub fn harmful(self) -> bool {
+        matches!(self, StatusKind::Corrosion | StatusKind::Corrupt)
+    }
+}
+
+/// An effect left on a player by an ability, which ticks at the end of every
+/// round after the one it was added in.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, Hash)]
+pub struct StatusEffect {
+    pub kind: StatusKind,
+    pub amount: u8,
+    /// The min of a harmful effect, the max of the others.
+    pub limit: u8,
+    /// Rounds the effect already ticked.
+    #[serde(default)]
+    pub ticks: u8,
+}
+
+impl StatusEffect {
+    /// Life or pillz the next tick takes or gives.
+    pub fn next_change(&self) -> u8 {
+        match self.kind {
+            StatusKind::Corrosion => self.amount.saturating_mul(self.ticks + 1),
+            StatusKind::Corrupt | StatusKind::Repair => self.amount,
+            StatusKind::Rebirth => self.amount.min(self.limit),
+        }
+    }
+}
+
+impl Display for StatusEffect {
+    fn fmt(&self, f: &mut Formatter) -> Result {
+        if self.kind.harmful() {
+            write!(
+                f,
+                "{:?} -{}, Min {}",
+                self.kind,
+                self.next_change(),
+                self.limit
+            )
+        } else {
+            write!(
+                f,
+                "{:?} +{}, Max {}",
+                self.kind,
+                self.next_change(),
+                self.limit
+            )
+        }
+    }
+}
+
+/// The status effects on a player, at most one of each kind.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize, Hash)]
+pub struct Statuses(StackVec4<StatusEffect>);
+
+impl Statuses {
+    pub fn add(&mut self, effect: StatusEffect) {
+        let existing = self.0.data[..self.0.len]
+            .iter_mut()
+            .flatten()
+            .find(|e| e.kind == effect.kind);
+        match existing {
+            Some(e) if effect.kind.stacks() => e.amount = e.amount.saturating_add(effect.amount),
+            Some(e) => *e = effect,
+            None => self.0.push(effect),
+        }
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &StatusEffect> {
+        self.0.data[..self.0.len].iter().flatten()
+    }
+
+    /// Applies every effect on `player` once, dropping the ones that ran out.
+    pub fn tick(player: &mut Player, log: &mut BattleLog) {
+        let Player {
+            player_type,
+            life,
+            pillz,
+            won,
+            statuses,
+            ..
+        } = player;
+
+        for i in (0..statuses.0.len).rev() {
+            let effect = statuses.0.data[i].as_mut().unwrap();
+            let (value, clamp): (_, fn(i32) -> u8) = match effect.kind.stat() {
+                Stat::Pillz => (&mut *pillz, Player::clamp_pillz),
+                _ => (&mut *life, Player::clamp_life),
+            };
+            let before = *value;
+            let change = effect.next_change() as i32;
+            let mut used = false;
+            match effect.kind {
+                StatusKind::Corrosion | StatusKind::Corrupt => {
+                    if before > effect.limit {
+                        *value = clamp(before as i32 - change).max(effect.limit);
+                    }
+                }
+                StatusKind::Repair => {
+                    if *won == RoundWin::LOSE && before < effect.limit {
+                        *value = clamp(before as i32 + change).min(effect.limit);
+                    }
+                }
+                StatusKind::Rebirth => {
+                    if before == 0 {
+                        *value = clamp(change);
+                        used = true;
+                    }
+                }
+            }
+            effect.ticks += 1;
+            log.push(BattleEvent::StatusTicked {
+                player: *player_type,
+                kind: effect.kind,
+                before,
+                after: *value,
+            });
+
+            if used || effect.kind.duration() == Some(effect.ticks) {
+                statuses.0.remove(i);
             }
         }
     }
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Hash)]
 pub struct Events {
     events: StackVec4<(EventTime, Ability)>,
     global: Option<StackVec4<(EventTime, Ability)>>,
@@ -38,9 +463,11 @@ impl Default for Events {
 }
 
 impl Events {
-    pub fn add(&mut self, ability: Ability) {
+    /// Queues an ability for this round. Returns `false` if it has nothing the
+    /// engine can execute.
+    pub fn add(&mut self, ability: Ability) -> bool {
         if ability.modifiers.len() == 0 {
-            println!("{}: {:#?}", "Failed to add ability".red(), ability);
+            false
         } else {
             match ability.ability_type {
                 AbilityType::Ability | AbilityType::Bonus => {
@@ -56,16 +483,19 @@ impl Events {
                         .unwrap()
                         .push((ability.event_time(), ability));
                 }
-                _ => (),
+                AbilityType::Global => self.add_global(ability),
             }
+            true
         }
     }
 
-    pub fn add_global(&mut self, ability: Ability) {
+    /// Adds a leader ability, which applies every round from now on as an
+    /// `AbilityType::Global` ability.
+    pub fn add_global(&mut self, mut ability: Ability) {
         if ability.modifiers.len() == 0 {
-            println!("{}: {:#?}", "Failed to add global ability".red(), ability);
             return;
         }
+        ability.ability_type = AbilityType::Global;
 
         if self.global == None {
             self.global = Some(Default::default());
@@ -74,12 +504,6 @@ impl Events {
             .as_mut()
             .unwrap()
             .push((ability.event_time(), ability));
-        // println!(
-        //     "{}: {:#?}",
-        //     "Global abilities".purple(),
-        //     self.global.unwrap()
-        // );
-        println!("{}: {:#?}", "Added global ability".green(), ability);
     }
 
     pub fn execute(&mut self, event: EventTime, data: &BattleData) {
@@ -126,30 +550,22 @@ impl Events {
 
                         let applied = modifier.applied.unwrap();
 
-                        // println!("Applied is some: {:?}", ability.ability_type);
                         if ability.ability_type == AbilityType::Ability {
-                            println!("{:?}", data.card.borrow());
                             // if data.card.borrow().ability.attr.is_blocked() == applied {
                             if data.card.borrow().ability.is_blocked() == applied {
-                                println!("{}: {:?}", "Undoing ability".red(), modifier);
                                 if applied {
-                                    println!("{}: {:?}", "Undoing bonus".red(), modifier);
                                     modifier.undo(data);
                                 } else {
-                                    println!("{}: {:?}", "Redoing bonus".yellow(), modifier);
                                     modifier.apply(data);
                                 }
                                 changed = true;
                             }
                         } else if ability.ability_type == AbilityType::Bonus {
-                            // println!("{:?}", data.card.borrow());
                             // if data.card.borrow().bonus.attr.is_blocked() == applied {
                             if data.card.borrow().bonus.is_blocked() == applied {
                                 if applied {
-                                    println!("{}: {:?}", "Undoing bonus".red(), modifier);
                                     modifier.undo(data);
                                 } else {
-                                    println!("{}: {:?}", "Redoing bonus".yellow(), modifier);
                                     modifier.apply(data);
                                 }
                                 changed = true;
@@ -189,6 +605,7 @@ impl Events {
 
 pub struct BattleData<'a> {
     pub round: u8,
+    pub day: bool,
     pub first: bool,
     pub player: &'a RefCell<&'a mut Player>,
     pub hand: &'a HandCell<'a>,
@@ -199,4 +616,17 @@ pub struct BattleData<'a> {
     pub opp_card: &'a RefCell<&'a mut Card>,
     pub opp_pillz_used: u8,
     pub events: &'a RefCell<&'a mut Events>,
+    pub log: &'a RefCell<BattleLog>,
+}
+
+impl BattleData<'_> {
+    /// The side whose abilities this data is applying.
+    #[inline]
+    pub fn player_type(&self) -> PlayerType {
+        self.player.borrow().player_type
+    }
+    #[inline]
+    pub fn log(&self, event: BattleEvent) {
+        self.log.borrow_mut().push(event);
+    }
 }
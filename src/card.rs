@@ -1,12 +1,13 @@
 #![allow(dead_code)]
 
 use std::{
+    array,
     cell::RefCell,
     collections::{HashMap, HashSet},
     convert::TryInto,
-    fs::File,
+    fmt::{Display, Formatter},
     ops::{Index, IndexMut},
-    path::Path,
+    sync::OnceLock,
 };
 
 use chrono::{Datelike, NaiveDateTime};
@@ -14,11 +15,12 @@ use colored::{Color, Colorize};
 use lazy_static::lazy_static;
 use rand::{seq::SliceRandom, thread_rng};
 use regex::Captures;
-use serde::Deserialize;
-use simd_json::from_reader;
+use serde::{Deserialize, Serialize};
 
 use crate::{
     ability::{Ability, ABILITIES, CLANS_REGEX},
+    assets::{self, AssetError},
+    modifiers::Keyword,
     types::{Clan, Rarity},
 };
 
@@ -29,14 +31,19 @@ pub struct CardData {
     #[serde(rename = "clan_id")]
     pub clan: Clan,
     pub level: u8,
+    pub level_min: u8,
     pub level_max: u8,
     pub power: u8,
     pub damage: u8,
+    #[serde(default)]
+    pub levels: Vec<CardLevel>,
     pub rarity: String,
     pub ability_id: u32,
     pub ability: String,
+    pub ability_unlock_level: u8,
     pub bonus_id: u32,
     pub bonus: String,
+    pub has_night_bonus: bool,
     pub release_date: u32,
 }
 
@@ -46,18 +53,33 @@ pub struct BaseCard {
     pub name: String,
     pub clan: Clan,
     pub level: u8,
+    pub level_min: u8,
     pub level_max: u8,
     pub power: u8,
     pub damage: u8,
+    pub levels: Vec<CardLevel>,
     pub rarity: Rarity,
     pub ability_id: u32,
     pub ability: String,
+    pub ability_unlock_level: u8,
     pub bonus_id: u32,
     pub bonus: String,
+    pub has_night_bonus: bool,
     pub year: u32,
 }
 
-#[derive(Clone, Copy, Debug)]
+/// Power and damage of a card at one evolution level.
+///
+/// `data.json` carries the stats of the card's max level, other levels need a
+/// `levels` table. Without one, the card can only be played at max level.
+#[derive(Clone, Copy, Debug, Deserialize)]
+pub struct CardLevel {
+    pub level: u8,
+    pub power: u8,
+    pub damage: u8,
+}
+
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
 pub struct CardAttr {
     pub cancelled: u8,
     protected: u8,
@@ -103,19 +125,21 @@ impl CardAttr {
     }
 }
 
-#[derive(Clone, Copy, Debug)]
+/// A power, damage or attack value. Wider than the stats printed on cards,
+/// since attack with 13 pillz, fury and attack bonuses goes past 255.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
 pub struct CardStat {
     pub attr: CardAttr,
-    pub base: u8,
-    pub value: u8,
+    pub base: u16,
+    pub value: u16,
 }
 
 impl CardStat {
     fn new(val: u8) -> Self {
         CardStat {
             attr: CardAttr::default(),
-            base: val,
-            value: val,
+            base: val as u16,
+            value: val as u16,
         }
     }
 }
@@ -130,42 +154,55 @@ impl Default for CardStat {
     }
 }
 
-lazy_static! {
-    static ref CARDS: Vec<BaseCard> = {
-        let data_file =
-            File::open(Path::new("./assets/data.json")).expect("file should open read only");
-        let mut cards: Vec<CardData> =
-            from_reader(data_file).expect("Error while reading JSON file");
-
-        let mut base_cards = Vec::with_capacity(cards.len());
-        for card in cards.iter_mut() {
-            let ability_str = card.ability.clone();
-            let ability = CLANS_REGEX.replace_all(ability_str.as_str(), |caps: &Captures| {
-                Clan::from(*&caps[1].parse::<u8>().unwrap())
-                    .short_name()
-                    .to_string()
-                    + " "
-            });
+static LOADED_CARDS: OnceLock<Vec<BaseCard>> = OnceLock::new();
 
-            base_cards.push(BaseCard {
-                id: card.id,
-                name: card.name.clone(),
-                clan: card.clan,
-                rarity: Rarity::from(&card.rarity),
-                year: card.year(),
-                level: card.level,
-                level_max: card.level_max,
-                power: card.power,
-                damage: card.damage,
-                ability_id: card.ability_id,
-                ability: ability.to_string(),
-                bonus_id: card.bonus_id,
-                bonus: card.bonus.clone(),
-            });
-        }
+/// Reads every card from `data.json` in the asset directory. Cards are only
+/// read once; later calls return the same list.
+pub fn load_cards() -> Result<&'static [BaseCard], AssetError> {
+    if let Some(cards) = LOADED_CARDS.get() {
+        return Ok(cards);
+    }
 
-        base_cards
-    };
+    let mut bytes = assets::read(assets::CARDS_FILE)?;
+    let cards: Vec<CardData> = simd_json::from_slice(&mut bytes)
+        .map_err(|e| AssetError::invalid(assets::CARDS_FILE, e))?;
+
+    let mut base_cards = Vec::with_capacity(cards.len());
+    for card in cards.iter() {
+        let ability_str = card.ability.clone();
+        let ability = CLANS_REGEX.replace_all(ability_str.as_str(), |caps: &Captures| {
+            Clan::from(*&caps[1].parse::<u8>().unwrap())
+                .short_name()
+                .to_string()
+                + " "
+        });
+
+        base_cards.push(BaseCard {
+            id: card.id,
+            name: card.name.clone(),
+            clan: card.clan,
+            rarity: Rarity::from(&card.rarity),
+            year: card.year(),
+            level: card.level,
+            level_min: card.level_min,
+            level_max: card.level_max,
+            power: card.power,
+            damage: card.damage,
+            levels: card.levels.clone(),
+            ability_id: card.ability_id,
+            ability: ability.to_string(),
+            ability_unlock_level: card.ability_unlock_level,
+            bonus_id: card.bonus_id,
+            bonus: card.bonus.clone(),
+            has_night_bonus: card.has_night_bonus,
+        });
+    }
+
+    Ok(LOADED_CARDS.get_or_init(|| base_cards))
+}
+
+lazy_static! {
+    static ref CARDS: &'static [BaseCard] = load_cards().unwrap_or_else(|e| panic!("{}", e));
     pub static ref ABILITY_STRINGS: HashMap<u32, String> = {
         let mut map = HashMap::new();
         for card in CARDS.iter() {
@@ -184,7 +221,7 @@ lazy_static! {
     static ref CARD_NAMES: HashMap<String, BaseCard> = {
         let mut map = HashMap::new();
         for card in CARDS.iter() {
-            map.insert(card.name.to_string().to_ascii_lowercase(), card.clone());
+            map.insert(fold_name(&card.name), card.clone());
         }
         map
     };
@@ -228,19 +265,216 @@ impl CardData {
     }
 }
 
+/// Most names suggested for an unknown card.
+const MAX_SUGGESTIONS: usize = 5;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum CardError {
+    /// No card has this name. `suggestions` holds the closest names.
+    UnknownName {
+        name: String,
+        suggestions: Vec<String>,
+    },
+    UnknownId(u32),
+    /// The card exists, but not at this level.
+    InvalidLevel {
+        name: String,
+        level: u8,
+        min: u8,
+        max: u8,
+    },
+    /// The level exists, but the assets have no power and damage for it.
+    UnknownStats {
+        name: String,
+        level: u8,
+    },
+}
+
+impl CardError {
+    /// The closest card names, empty unless the name is unknown.
+    pub fn suggestions(&self) -> &[String] {
+        match self {
+            CardError::UnknownName { suggestions, .. } => suggestions,
+            CardError::UnknownId(_)
+            | CardError::InvalidLevel { .. }
+            | CardError::UnknownStats { .. } => &[],
+        }
+    }
+}
+
+impl Display for CardError {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        match self {
+            CardError::UnknownName { name, suggestions } => {
+                write!(f, "Unknown card {:?}", name)?;
+                if !suggestions.is_empty() {
+                    write!(f, ", did you mean {}?", suggestions.join(", "))?;
+                }
+                Ok(())
+            }
+            CardError::UnknownId(id) => write!(f, "Unknown card id {}", id),
+            CardError::InvalidLevel {
+                name,
+                level,
+                min,
+                max,
+            } => write!(
+                f,
+                "{} can't be level {}, levels are {}..={}",
+                name, level, min, max
+            ),
+            CardError::UnknownStats { name, level } => write!(
+                f,
+                "No power and damage for {} at level {} in the assets",
+                name, level
+            ),
+        }
+    }
+}
+
+impl std::error::Error for CardError {}
+
+/// Lowercases a card name and strips its accents, so "mecanicles" finds
+/// "Mecaniclès".
+fn fold_name(name: &str) -> String {
+    name.trim()
+        .chars()
+        .flat_map(char::to_lowercase)
+        .map(|c| match c {
+            'à' | 'á' | 'â' | 'ã' | 'ä' | 'å' => 'a',
+            'ç' => 'c',
+            'è' | 'é' | 'ê' | 'ë' => 'e',
+            'ì' | 'í' | 'î' | 'ï' => 'i',
+            'ñ' => 'n',
+            'ò' | 'ó' | 'ô' | 'õ' | 'ö' => 'o',
+            'ù' | 'ú' | 'û' | 'ü' => 'u',
+            'ý' | 'ÿ' => 'y',
+            c => c,
+        })
+        .collect()
+}
+
+/// Levenshtein distance between two strings, counted in chars.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = diagonal + (ca != *cb) as usize;
+            diagonal = row[j + 1];
+            row[j + 1] = substitution.min(row[j] + 1).min(diagonal + 1);
+        }
+    }
+    row[b.len()]
+}
+
+/// The card names closest to `name`: names starting with it first, then
+/// names within a few typos, nearest first.
+pub fn suggest_names(name: &str) -> Vec<String> {
+    let folded = fold_name(name);
+    if folded.is_empty() {
+        return Vec::new();
+    }
+    let max_distance = (folded.chars().count() / 3).max(2);
+
+    let mut ranked: Vec<(bool, usize, &str)> = CARD_NAMES
+        .iter()
+        .filter_map(|(key, card)| {
+            if key.starts_with(&folded) {
+                return Some((false, key.len(), card.name.as_str()));
+            }
+            let distance = edit_distance(&folded, key);
+            (distance <= max_distance).then_some((true, distance, card.name.as_str()))
+        })
+        .collect();
+    ranked.sort();
+    ranked
+        .into_iter()
+        .take(MAX_SUGGESTIONS)
+        .map(|(_, _, name)| name.to_string())
+        .collect()
+}
+
 impl BaseCard {
+    pub fn find_id(id: u32) -> Result<Self, CardError> {
+        CARD_IDS.get(&id).cloned().ok_or(CardError::UnknownId(id))
+    }
+    /// Looks up a card by name, ignoring case and accents.
+    pub fn find_name(name: &str) -> Result<Self, CardError> {
+        CARD_NAMES
+            .get(&fold_name(name))
+            .cloned()
+            .ok_or_else(|| CardError::UnknownName {
+                name: name.to_string(),
+                suggestions: suggest_names(name),
+            })
+    }
+    /// Looks up a card by name, with an optional level suffix, e.g. `"Orka:3"`.
+    pub fn find_name_level(name: &str) -> Result<(Self, u8), CardError> {
+        if let Some((name, level)) = name.rsplit_once(':') {
+            if let Ok(level) = level.trim().parse::<u8>() {
+                let card = BaseCard::find_name(name)?;
+                card.check_level(level)?;
+                return Ok((card, level));
+            }
+        }
+        let card = BaseCard::find_name(name)?;
+        let level = card.level;
+        Ok((card, level))
+    }
     pub fn get_id(id: u32) -> Self {
-        CARD_IDS.get(&id).unwrap().clone()
+        BaseCard::find_id(id).unwrap_or_else(|e| panic!("{}", e))
     }
     pub fn get_name(name: &str) -> Self {
-        CARD_NAMES
-            .get(&name.to_string().to_ascii_lowercase())
-            .unwrap()
-            .clone()
+        BaseCard::find_name(name).unwrap_or_else(|e| panic!("{}", e))
+    }
+    pub fn get_name_level(name: &str) -> (Self, u8) {
+        BaseCard::find_name_level(name).unwrap_or_else(|e| panic!("{}", e))
+    }
+    /// Power and damage of the card at `level`, if the assets have them.
+    pub fn stats(&self, level: u8) -> Option<(u8, u8)> {
+        if level == self.level {
+            return Some((self.power, self.damage));
+        }
+        self.levels
+            .iter()
+            .find(|stats| stats.level == level)
+            .map(|stats| (stats.power, stats.damage))
     }
     pub fn to_card(&self, index: usize) -> Card {
         Card::from(self, index)
     }
+    /// Whether the card can be played at `level`: within its levels, and
+    /// with known stats.
+    pub fn check_level(&self, level: u8) -> Result<(), CardError> {
+        if !(self.level_min..=self.level_max).contains(&level) {
+            return Err(CardError::InvalidLevel {
+                name: self.name.clone(),
+                level,
+                min: self.level_min,
+                max: self.level_max,
+            });
+        }
+        match self.stats(level) {
+            Some(_) => Ok(()),
+            None => Err(CardError::UnknownStats {
+                name: self.name.clone(),
+                level,
+            }),
+        }
+    }
+    pub fn to_card_level(&self, index: usize, level: u8) -> Card {
+        self.try_to_card_level(index, level)
+            .unwrap_or_else(|e| panic!("{}", e))
+    }
+    /// Like `to_card_level`, but returns a level the card can't have as an
+    /// error.
+    pub fn try_to_card_level(&self, index: usize, level: u8) -> Result<Card, CardError> {
+        self.check_level(level)?;
+        Ok(Card::from_level(self, index, level))
+    }
 }
 
 // impl CardAbility {
@@ -263,7 +497,7 @@ impl BaseCard {
 //     }
 // }
 
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
 pub struct Card {
     pub played: bool,
     pub won: bool,
@@ -279,6 +513,12 @@ pub struct Card {
     pub attack: CardStat,
     pub life: CardAttr,
     pub pillz: CardAttr,
+    /// The player's leader ability, cancelled for the round by `Cancel Leader`.
+    #[serde(default)]
+    pub leader: CardAttr,
+    /// Bit set of the `Keyword`s given to the card this round.
+    #[serde(default)]
+    pub keywords: u16,
 }
 
 fn split_lines(s: &String, len: usize, min: usize) -> Vec<String> {
@@ -335,22 +575,60 @@ impl Card {
     pub fn year(&self) -> u32 {
         self.base().year
     }
+    #[inline(always)]
+    pub fn has_night_bonus(&self) -> bool {
+        self.base().has_night_bonus
+    }
+    #[inline]
+    pub fn add_keyword(&mut self, keyword: Keyword) {
+        self.keywords |= 1 << keyword as u8;
+    }
+    /// Whether the card has `keyword` this round. Leader keywords don't count
+    /// while the leader is blocked.
+    #[inline]
+    pub fn keyword(&self, keyword: Keyword) -> bool {
+        self.keywords & (1 << keyword as u8) != 0
+            && !(keyword.is_leader() && self.is_leader_blocked())
+    }
+    /// Whether the player's leader ability is stopped this round: by
+    /// `Cancel Leader`, or, when the leader itself is played, by stopping its
+    /// ability.
+    #[inline]
+    pub fn is_leader_blocked(&self) -> bool {
+        self.leader.is_blocked() || (self.clan() == Clan::Leader && self.ability.is_blocked())
+    }
     pub fn from(data: &BaseCard, index: usize) -> Self {
+        Card::from_level(data, index, data.level)
+    }
+    /// The card at `level`, which `BaseCard::check_level` must accept.
+    pub fn from_level(data: &BaseCard, index: usize, level: u8) -> Self {
+        debug_assert!(data.check_level(level).is_ok());
+
+        let (power, damage) = data.stats(level).expect("stats of a checked level");
+        // The ability stays locked until the card evolves far enough
+        let ability_id = if level < data.ability_unlock_level {
+            0
+        } else {
+            data.ability_id
+        };
+
         Card {
             played: false,
             won: false,
             index,
             id: data.id,
-            level: data.level,
-            ability_id: data.ability_id,
+            level,
+            ability_id,
             ability: CardAttr::default(),
             bonus_id: data.bonus_id,
             bonus: CardAttr::default(),
-            power: CardStat::new(data.power),
-            damage: CardStat::new(data.damage),
+            power: CardStat::new(power),
+            damage: CardStat::new(damage),
             attack: CardStat::default(),
             life: CardAttr::default(),
             pillz: CardAttr::default(),
+            leader: CardAttr::default(),
+            keywords: 0,
         }
     }
     pub fn print(&self, x: usize, shift_up: bool, playing: bool) {
@@ -523,7 +801,7 @@ impl Card {
     }
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
 pub struct Hand {
     pub cards: [Card; 4],
     pub clan_count: [u8; 4],
@@ -645,13 +923,16 @@ impl Hand {
         leader
     }
     pub fn random_hand_clan(clan: Clan) -> Self {
-        let mut cards = CARD_CLANS[&clan]
+        let cards = CARD_CLANS[&clan]
             .choose_multiple(&mut thread_rng(), 4)
             .enumerate()
             .map(|(index, data)| data.to_card(index))
             .collect::<Vec<Card>>()
             .try_into()
             .unwrap();
+        Hand::new(cards)
+    }
+    pub fn new(mut cards: [Card; 4]) -> Self {
         let (clan_count, oculus_clan) = Hand::clan_counts(&mut cards);
         Hand {
             cards,
@@ -660,32 +941,55 @@ impl Hand {
         }
     }
     pub fn from_ids(i1: u32, i2: u32, i3: u32, i4: u32) -> Self {
-        let mut cards = [
+        Hand::new([
             BaseCard::get_id(i1).to_card(0),
             BaseCard::get_id(i2).to_card(1),
             BaseCard::get_id(i3).to_card(2),
             BaseCard::get_id(i4).to_card(3),
-        ];
-        let (clan_count, oculus_clan) = Hand::clan_counts(&mut cards);
-        Hand {
-            cards,
-            clan_count,
-            oculus_clan,
+        ])
+    }
+    /// Builds a hand from `(id, level)` pairs.
+    pub fn from_ids_levels(cards: [(u32, u8); 4]) -> Self {
+        Hand::try_from_ids_levels(cards).unwrap_or_else(|e| panic!("{}", e))
+    }
+    /// Like `from_ids_levels`, but returns the first unknown id or invalid
+    /// level as an error.
+    pub fn try_from_ids_levels(cards: [(u32, u8); 4]) -> Result<Self, CardError> {
+        let mut base = Vec::with_capacity(4);
+        for (id, level) in cards {
+            base.push((BaseCard::find_id(id)?, level));
+        }
+        Hand::try_from_base_levels(base.try_into().unwrap())
+    }
+    /// Builds a hand from cards already looked up, e.g. ones given a
+    /// `levels` table, returning the first invalid level as an error.
+    pub fn try_from_base_levels(cards: [(BaseCard, u8); 4]) -> Result<Self, CardError> {
+        let mut hand = Vec::with_capacity(4);
+        for (i, (card, level)) in cards.into_iter().enumerate() {
+            hand.push(card.try_to_card_level(i, level)?);
         }
+        Ok(Hand::new(hand.try_into().unwrap()))
     }
+    /// Builds a hand from card names. A name may end with `:<level>`, e.g.
+    /// `"Orka:3"`, to play the card below its max level.
     pub fn from_names(c1: &str, c2: &str, c3: &str, c4: &str) -> Self {
-        let mut cards = [
-            BaseCard::get_name(c1).to_card(0),
-            BaseCard::get_name(c2).to_card(1),
-            BaseCard::get_name(c3).to_card(2),
-            BaseCard::get_name(c4).to_card(3),
-        ];
-        let (clan_count, oculus_clan) = Hand::clan_counts(&mut cards);
-        Hand {
-            cards,
-            clan_count,
-            oculus_clan,
+        Hand::try_from_names([c1, c2, c3, c4]).unwrap_or_else(|e| panic!("{}", e))
+    }
+    /// Like `from_names`, but returns the first unknown name or invalid level
+    /// as an error.
+    pub fn try_from_names(names: [&str; 4]) -> Result<Self, CardError> {
+        let mut cards = Vec::with_capacity(4);
+        for name in names {
+            cards.push(BaseCard::find_name_level(name)?);
         }
+        Hand::try_from_base_levels(cards.try_into().unwrap())
+    }
+    /// Builds a hand from `(name, level)` pairs.
+    pub fn from_names_levels(cards: [(&str, u8); 4]) -> Self {
+        Hand::new(array::from_fn(|i| {
+            let (name, level) = cards[i];
+            BaseCard::get_name(name).to_card_level(i, level)
+        }))
     }
     pub fn to_handcell(&mut self) -> HandCell {
         let [a, b, c, d] = &mut self.cards;
@@ -715,3 +1019,126 @@ impl HandCell<'_> {
         self.clan_count[index]
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{edit_distance, suggest_names, BaseCard, CardError, CardLevel, Hand};
+    use crate::{
+        game::{Game, RoundWin},
+        output::Silent,
+    };
+
+    #[test]
+    fn finds_names_without_accents() {
+        assert_eq!(
+            BaseCard::find_name("mecanicles").unwrap().name,
+            "Mecaniclès"
+        );
+        assert_eq!(BaseCard::find_name_level("JEREMY FRANCOIS:5").unwrap().1, 5);
+        assert_eq!(edit_distance("kitten", "sitting"), 3);
+    }
+
+    #[test]
+    fn suggests_close_names() {
+        match BaseCard::find_name("Ork") {
+            Err(CardError::UnknownName { name, suggestions }) => {
+                assert_eq!(name, "Ork");
+                assert_eq!(suggestions[0], "Orka");
+            }
+            other => panic!("expected an unknown name, got {:?}", other),
+        }
+        assert!(suggest_names("Hollow Spike").contains(&"Hollow Spyke".to_string()));
+        assert!(suggest_names("zzzzzzzzzzzz").is_empty());
+
+        let error = Hand::try_from_names(["Figaro", "Elvis", "Danea", "Vivian"]).unwrap_err();
+        assert!(error.suggestions().contains(&"Danae".to_string()));
+        assert!(error
+            .to_string()
+            .starts_with("Unknown card \"Danea\", did you mean"));
+        assert_eq!(BaseCard::find_id(0).unwrap_err(), CardError::UnknownId(0));
+    }
+
+    #[test]
+    fn rejects_levels_out_of_range() {
+        let orka = BaseCard::find_name("Orka").unwrap();
+        let error = BaseCard::find_name_level("Orka:9").unwrap_err();
+        assert_eq!(
+            error,
+            CardError::InvalidLevel {
+                name: "Orka".to_string(),
+                level: 9,
+                min: orka.level_min,
+                max: orka.level_max,
+            }
+        );
+        assert!(error.suggestions().is_empty());
+        assert!(orka.try_to_card_level(0, 0).is_err());
+        assert!(Hand::try_from_ids_levels([(orka.id, 9); 4]).is_err());
+        assert!(Hand::try_from_ids_levels([(0, 1); 4]).is_err());
+    }
+
+    #[test]
+    fn needs_stats_below_max_level() {
+        let mut orka = BaseCard::find_name("Orka").unwrap();
+        assert_eq!(
+            orka.check_level(2),
+            Err(CardError::UnknownStats {
+                name: "Orka".to_string(),
+                level: 2,
+            })
+        );
+        assert!(matches!(
+            BaseCard::find_name_level("Orka:2"),
+            Err(CardError::UnknownStats { .. })
+        ));
+
+        orka.levels.push(CardLevel {
+            level: 2,
+            power: 3,
+            damage: 2,
+        });
+        let card = orka.try_to_card_level(0, 2).unwrap();
+        assert_eq!((card.power.base, card.damage.base), (3, 2));
+        assert_eq!(card.ability_id, 0);
+    }
+
+    /// Romella's Equalizer multiplies by the level of the card it faces, and
+    /// ties go to the lower level.
+    #[test]
+    fn levels_feed_the_battle() {
+        let game_with = |poe: BaseCard, level| {
+            let h1 = Hand::from_names("Romella", "Elvis", "Cell", "Figaro");
+            let names =
+                ["John O Clock", "Vivian", "Danae"].map(|n| BaseCard::find_name(n).unwrap());
+            let [c2, c3, c4] = names.map(|card| {
+                let level = card.level;
+                (card, level)
+            });
+            let h2 = Hand::try_from_base_levels([(poe, level), c2, c3, c4]).unwrap();
+            let mut game = Game::new(h1, h2);
+            game.output = &Silent;
+            game
+        };
+        let mut poe = BaseCard::find_name("Poe").unwrap();
+        poe.levels.push(CardLevel {
+            level: 1,
+            power: 6,
+            damage: 2,
+        });
+
+        // Poe at its max level 2 doubles Romella's bonus
+        let mut game = game_with(poe.clone(), 2);
+        game.select(0, 0, false);
+        game.select(0, 0, false);
+        assert_eq!(game.h1[0].attack.value, 7 + 5 * 2);
+
+        // At level 1 both attack 12, and Poe wins on its lower level
+        let mut game = game_with(poe, 1);
+        game.select(0, 0, false);
+        game.select(0, 1, false);
+        assert_eq!(game.h1[0].attack.value, 7 + 5);
+        assert_eq!(game.h2[0].attack.value, 6 * 2);
+        assert_eq!(game.p2.won, RoundWin::WIN);
+        assert_eq!(game.p1.life, 12 - 2);
+    }
+}
@@ -0,0 +1,348 @@
+use std::{io, path::PathBuf};
+
+use clap::{value_parser, Args, Parser, Subcommand};
+use colored::Colorize;
+
+use crate::{
+    assets,
+    card::{BaseCard, Hand},
+    compiler::Compiler,
+    coverage::Coverage,
+    game::{Game, GameStatus, PlayerType, Selection, MAX_PILLZ},
+    replay::Replay,
+    snapshot::Snapshot,
+    solver::{self, Engine, Evaluation, Options, SelectionResult},
+};
+
+#[derive(Debug, Parser)]
+#[command(about = "Urban Rivals battle engine and solver")]
+pub struct Cli {
+    /// Threads used by the solver. Defaults to one per core.
+    #[arg(long, global = true)]
+    pub threads: Option<usize>,
+    /// Directory holding data.json and compiled.json.
+    #[arg(long, global = true)]
+    pub assets: Option<PathBuf>,
+    /// Solver engine used for advice. The server uses it for games that
+    /// don't pick one.
+    #[arg(long, global = true, value_enum, default_value_t)]
+    pub solver: Engine,
+    /// How the solver tells apart games with the same result: `result`, or
+    /// weighted terms of `life`, `pillz` and `ko`, e.g. `life,ko=5`.
+    #[arg(long, global = true, default_value = "result")]
+    pub eval: Evaluation,
+    #[command(subcommand)]
+    pub command: Command,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// Play a game from the console, with the solver's advice after each move.
+    Play(GameArgs),
+    /// Print the solver's pick for a position.
+    Solve {
+        #[command(flatten)]
+        game: GameArgs,
+        /// Selections to play before solving, e.g. --moves "0 2" "1 3 true".
+        #[arg(long, num_args = 1..)]
+        moves: Vec<String>,
+        /// Print the optimal mixed strategy over the hidden pillz and fury of
+        /// every round left, and its value, instead of a single pick.
+        #[arg(long)]
+        mixed: bool,
+    },
+    /// Start the HTTP server.
+    Serve {
+        #[arg(long, default_value = "127.0.0.1")]
+        host: String,
+        #[arg(long, default_value_t = 8080)]
+        port: u16,
+    },
+    /// Step through a recorded match, flagging moves that differ from the solver.
+    Replay { file: PathBuf },
+    /// Print a card. Add `:<level>` to the name to print it below max level.
+    Card { name: String },
+    /// Play a whole game with both sides following the solver.
+    Simulate(GameArgs),
+    /// Compile the abilities of every card in data.json to compiled.json.
+    Compile {
+        /// Where to write the abilities. Defaults to compiled.json in the
+        /// asset directory.
+        #[arg(long)]
+        output: Option<PathBuf>,
+    },
+    /// List the abilities the engine can't simulate faithfully, and the
+    /// cards and clans that have them.
+    Coverage {
+        /// Print the report as JSON.
+        #[arg(long)]
+        json: bool,
+    },
+}
+
+#[derive(Debug, Args)]
+pub struct GameArgs {
+    /// Your 4 cards then the opponent's 4. Add `:<level>` to a name to play it
+    /// below max level.
+    #[arg(
+        num_args = 8,
+        required_unless_present = "snapshot",
+        value_name = "CARD"
+    )]
+    pub cards: Vec<String>,
+    /// Resume the game saved in a snapshot file instead, JSON or CBOR.
+    #[arg(long, conflicts_with_all = ["cards", "levels", "opponent_first", "night", "life", "pillz"])]
+    pub snapshot: Option<PathBuf>,
+    /// Levels of the 8 cards, comma separated. 0 keeps the card's level.
+    #[arg(long, value_delimiter = ',')]
+    pub levels: Vec<u8>,
+    /// The opponent plays first.
+    #[arg(long)]
+    pub opponent_first: bool,
+    /// Play at Night instead of during the Day.
+    #[arg(long)]
+    pub night: bool,
+    /// Starting life of both players, at least 1.
+    #[arg(long, default_value_t = 12, value_parser = value_parser!(u8).range(1..))]
+    pub life: u8,
+    /// Starting pillz of both players, at most 31.
+    #[arg(long, default_value_t = 12, value_parser = value_parser!(u8).range(..=MAX_PILLZ as i64))]
+    pub pillz: u8,
+}
+
+impl GameArgs {
+    pub fn game(&self) -> Result<Game, String> {
+        if let Some(path) = &self.snapshot {
+            return Snapshot::load(path)
+                .and_then(Game::from_snapshot)
+                .map_err(|e| e.to_string());
+        }
+        if !self.levels.is_empty() && self.levels.len() != 8 {
+            return Err(format!(
+                "--levels needs 8 values, got {}",
+                self.levels.len()
+            ));
+        }
+        let names: Vec<String> = self
+            .cards
+            .iter()
+            .enumerate()
+            .map(|(i, name)| match self.levels.get(i).copied() {
+                Some(level) if level != 0 => {
+                    let name = name.rsplit_once(':').map_or(name.as_str(), |(n, _)| n);
+                    format!("{}:{}", name, level)
+                }
+                _ => name.clone(),
+            })
+            .collect();
+        let hand = |names: &[String]| {
+            Hand::try_from_names([&names[0], &names[1], &names[2], &names[3]])
+                .map_err(|e| e.to_string())
+        };
+        let h1 = hand(&names[..4])?;
+        let h2 = hand(&names[4..])?;
+
+        let mut game = Game::new(h1, h2);
+        game.flip = self.opponent_first as u8;
+        game.day = !self.night;
+        game.p1.start_with(self.life, self.pillz);
+        game.p2.start_with(self.life, self.pillz);
+        Ok(game)
+    }
+}
+
+pub fn play(mut game: Game, options: &Options) {
+    game.print_status();
+
+    // Replays start from the first round, so a resumed game isn't recorded
+    let mut replay = (game.round == 0 && !game.has_someone_selected()).then(|| {
+        let replay_path = Replay::new_path("");
+        println!("Recording replay to {}", replay_path.display());
+        (Replay::new(&game), replay_path)
+    });
+
+    if game.flip == 0 {
+        solver::middle(&game, options);
+    }
+
+    println!("{} turn", game.get_turn_name());
+    for line in io::stdin().lines() {
+        let mut input = line.unwrap();
+
+        if let Some(path) = input.strip_prefix("save ") {
+            match game.snapshot().save(path.trim().as_ref()) {
+                Ok(()) => println!("Saved snapshot to {}", path.trim()),
+                Err(e) => eprintln!("Failed to save snapshot: {}", e),
+            }
+            continue;
+        }
+
+        let cancelled: bool;
+        if input.as_str() == "cancel" {
+            if let Some((replay, _)) = &mut replay {
+                replay.cancel(&game);
+            }
+            game.clear_selection();
+            game.print_status();
+            continue;
+        } else if input.starts_with("x ") {
+            input = input[2..].to_string();
+            if let Some((replay, _)) = &mut replay {
+                replay.cancel(&game);
+            }
+            game.clear_selection();
+            cancelled = true;
+        } else {
+            cancelled = false;
+        }
+
+        let Some(Selection { index, pillz, fury }) = Selection::parse(input) else {
+            continue;
+        };
+
+        if !game.can_select(index, pillz, fury) {
+            continue;
+        }
+
+        if let Some(log) = game.select(index, pillz, fury) {
+            print!("{}", log);
+        }
+        if let Some((replay, replay_path)) = &mut replay {
+            replay.push(Selection { index, pillz, fury });
+            if let Err(e) = replay.save(replay_path) {
+                eprintln!("Failed to save replay: {}", e);
+            }
+        }
+        if game.status() != GameStatus::Playing {
+            break;
+        }
+
+        let turn = game.get_turn();
+
+        if game.round == 0 {
+            if !cancelled && turn == PlayerType::Player {
+                solver::middle(&game, options);
+            }
+        } else {
+            let best = solver::solve(&game, options);
+
+            match (best, turn) {
+                (SelectionResult::Player(_), PlayerType::Opponent)
+                | (SelectionResult::Opponent(_), PlayerType::Player) => {
+                    solver::middle(&game, options);
+                }
+                (_, _) => println!("{}", best),
+            }
+        }
+
+        println!("{} turn", game.get_turn_name());
+    }
+    game.print_status();
+}
+
+pub fn solve(
+    mut game: Game,
+    moves: &[String],
+    mixed: bool,
+    options: &Options,
+) -> Result<(), String> {
+    for input in moves {
+        let Some(Selection { index, pillz, fury }) = Selection::parse(input.clone()) else {
+            return Err(format!("Invalid selection {:?}", input));
+        };
+        if game.status() != GameStatus::Playing || !game.can_select(index, pillz, fury) {
+            return Err(format!("Selection {:?} can't be played", input));
+        }
+        if let Some(log) = game.select(index, pillz, fury) {
+            print!("{}", log);
+        }
+    }
+
+    game.print_status();
+    if game.status() != GameStatus::Playing {
+        return Ok(());
+    }
+
+    println!("{} turn", game.get_turn_name());
+    if mixed {
+        let strategy = solver::mixed(&game, options);
+        for choice in strategy.choices {
+            println!("{:5.1}% {}", choice.probability * 100f32, choice.selection);
+        }
+        println!(
+            "{} {:.2}",
+            " Value ".black().on_bright_green(),
+            strategy.value
+        );
+        return Ok(());
+    }
+    let best = solver::recommend(&game, options);
+    println!("{} {}", " Best ".black().on_bright_green(), best);
+    Ok(())
+}
+
+pub fn simulate(mut game: Game, options: &Options) {
+    game.print_status();
+    while game.status() == GameStatus::Playing {
+        println!("{} turn", game.get_turn_name());
+        let Selection { index, pillz, fury } = solver::recommend(&game, options);
+        if let Some(log) = game.select(index, pillz, fury) {
+            print!("{}", log);
+        }
+    }
+    game.print_status();
+}
+
+pub fn card(name: &str) -> Result<(), String> {
+    let (base, level) = BaseCard::find_name_level(name).map_err(|e| e.to_string())?;
+    base.to_card_level(0, level).print(0, false, false);
+    println!(
+        "{} #{}  {:?}  Level {} ({}-{})",
+        base.name.bold(),
+        base.id,
+        base.clan,
+        level,
+        base.level_min,
+        base.level_max
+    );
+    println!("{} {}", "Ability".bright_blue(), base.ability);
+    if level < base.ability_unlock_level {
+        println!("        Unlocks at level {}", base.ability_unlock_level);
+    }
+    println!("{} {}", "Bonus".bright_green(), base.bonus);
+    Ok(())
+}
+
+pub fn compile(output: Option<PathBuf>) -> Result<(), String> {
+    let report = Compiler::load()
+        .and_then(|compiler| compiler.compile_cards())
+        .map_err(|e| e.to_string())?;
+    for (id, (raw, e)) in report.failed.iter() {
+        println!("{} #{} {:?}: {}", "Failed".red(), id, raw, e);
+    }
+
+    let path = output.unwrap_or_else(|| assets::path(assets::ABILITIES_FILE));
+    report
+        .save(&path)
+        .map_err(|e| format!("Can't write {}: {}", path.display(), e))?;
+    println!(
+        "Compiled {} abilities to {}, {} failed",
+        report.abilities.len(),
+        path.display(),
+        report.failed.len()
+    );
+    Ok(())
+}
+
+pub fn coverage(json: bool) -> Result<(), String> {
+    let coverage = Coverage::load().map_err(|e| e.to_string())?;
+    if json {
+        let json = serde_json::to_string_pretty(&coverage).map_err(|e| e.to_string())?;
+        println!("{}", json);
+    } else if coverage.is_empty() {
+        println!("Every card ability is covered");
+    } else {
+        print!("{}", coverage);
+    }
+    Ok(())
+}
@@ -0,0 +1,780 @@
+use std::{
+    collections::BTreeMap,
+    fmt::{Display, Formatter},
+    fs::File,
+    io,
+    path::Path,
+};
+
+use regex::Regex;
+use serde::Serialize;
+use serde_json::{Map, Value};
+use tinyvec::ArrayVec;
+
+use crate::{
+    ability::{Ability, AbilityType, Condition},
+    assets::{self, AssetError},
+    battle::{StatusEffect, StatusKind},
+    card::CardData,
+    modifiers::{
+        BasicModifier, Cancel, CancelLeaderModifier, CancelModifier, Copy, CopyModifier, EventTime,
+        Exchange, ExchangeModifier, Keyword, KeywordModifier, Modifier, Per, Protect,
+        ProtectionModifier, RecoverModifier, Stat, StatusModifier, TuneOutModifier,
+    },
+};
+
+const MIN: i32 = -1000;
+const MAX: i32 = 1000;
+
+/// Effects that stay on the opponent or the player after the round.
+const STATUSES: [&str; 12] = [
+    "Poison",
+    "Toxin",
+    "Heal",
+    "Regen",
+    "Consume",
+    "Dope",
+    "Mindwipe",
+    "Combust",
+    "Corrosion",
+    "Corrupt",
+    "Rebirth",
+    "Repair",
+];
+
+/// Leader abilities, which stay in play for the whole game.
+const LEADERS: [&str; 8] = [
+    "Counter-Attack",
+    "Tie-Break",
+    "Bypass",
+    "Remove Ability Conditions",
+    "Illusion",
+    "Hazard",
+    "Limitless",
+    "Overdose",
+];
+
+#[derive(Debug)]
+pub enum CompileError {
+    /// The normalized ability isn't in `abilityTree.json`.
+    Grammar(String),
+    /// More conditions than an `Ability` holds.
+    Conditions(usize),
+}
+
+impl Display for CompileError {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        match self {
+            CompileError::Grammar(text) => write!(f, "{:?} is not in the ability grammar", text),
+            CompileError::Conditions(n) => write!(f, "{} conditions, at most 3 are supported", n),
+        }
+    }
+}
+
+impl std::error::Error for CompileError {}
+
+/// The ability grammar from `abilityTree.json`. Every key is a phrase where
+/// `x` stands for a number and `+x`/`-x` for a signed one. A `0` ends the
+/// phrase and a `<` key marks a node where the phrase may also end.
+#[derive(Debug, Clone)]
+pub struct Grammar(Map<String, Value>);
+
+impl Grammar {
+    pub fn load() -> Result<Self, AssetError> {
+        let bytes = assets::read(assets::GRAMMAR_FILE)?;
+        serde_json::from_slice(&bytes)
+            .map(Grammar)
+            .map_err(|e| AssetError::invalid(assets::GRAMMAR_FILE, e))
+    }
+
+    pub fn accepts(&self, text: &str) -> bool {
+        let tokens: Vec<&str> = text.split(' ').collect();
+        Grammar::walk(&self.0, &tokens)
+    }
+
+    fn walk(node: &Map<String, Value>, tokens: &[&str]) -> bool {
+        if tokens.is_empty() {
+            return node.contains_key("<");
+        }
+
+        node.iter()
+            .filter(|(key, _)| *key != "<")
+            .any(|(key, child)| {
+                let words: Vec<&str> = key.split(' ').collect();
+                if words.len() > tokens.len()
+                    || !words.iter().zip(tokens).all(|(w, t)| Grammar::word(w, t))
+                {
+                    return false;
+                }
+
+                let rest = &tokens[words.len()..];
+                match child {
+                    Value::Object(child) => Grammar::walk(child, rest),
+                    _ => rest.is_empty(),
+                }
+            })
+    }
+
+    fn word(word: &str, token: &str) -> bool {
+        match word {
+            "x" => token.parse::<u32>().is_ok(),
+            "+x" => token.starts_with('+') && token[1..].parse::<u32>().is_ok(),
+            "-x" => token.starts_with('-') && token[1..].parse::<u32>().is_ok(),
+            _ => word == token,
+        }
+    }
+}
+
+/// An ability string split into its conditions and its effect, with the
+/// effect rewritten to the form used by the grammar, e.g.
+/// `Courage: -2 Opp. Dmg, Min 2` is `Courage` and `-2 Damage Min 2`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Normalized {
+    pub conditions: Vec<String>,
+    pub text: String,
+    /// The effect named the opponent, e.g. `-2 Opp Damage`.
+    pub opp: bool,
+}
+
+impl Normalized {
+    pub fn new(raw: &str) -> Self {
+        let mut rest = raw.trim().replace(';', ":");
+        let mut conditions = Vec::new();
+
+        let clans = Regex::new(r"^(Versus\s*)?((?:\[[Cc]lan:\d+\])+)\s*:?").unwrap();
+        if let Some(caps) = clans.captures(&rest) {
+            let tags = caps[2].replace("[clan:", "[Clan:");
+            conditions.push(match caps.get(1) {
+                Some(_) => format!("Versus {}", tags),
+                None => tags,
+            });
+            rest = rest[caps[0].len()..].to_string();
+        }
+
+        let mut parts: Vec<&str> = rest.split(':').map(str::trim).collect();
+        while parts.len() > 1 {
+            match Normalized::condition(parts[0]) {
+                Some(condition) => {
+                    conditions.push(condition);
+                    parts.remove(0);
+                }
+                None => break,
+            }
+        }
+
+        let (text, opp) = Normalized::effect(&parts.join(" "));
+        Normalized {
+            conditions,
+            text,
+            opp,
+        }
+    }
+
+    fn condition(s: &str) -> Option<String> {
+        let name = match s {
+            "Vict. Or Def." => "Victory Or Defeat",
+            "Conf." | "Confid." => "Confidence",
+            "Repris." => "Reprisal",
+            "Asymm." => "Asymmetry",
+            _ => s,
+        };
+        Condition::from_name(name).map(|_| name.to_string())
+    }
+
+    fn effect(s: &str) -> (String, bool) {
+        let mut tokens: Vec<String> = Vec::new();
+        let mut join = false;
+        for word in s.split(|c: char| c.is_whitespace() || c == ',') {
+            let word = match word {
+                "Opp." => "Opp",
+                "Pillz." => "Pillz",
+                "Max." => "Max",
+                "Min." | "min" => "Min",
+                "Pow" | "Pow." => "Power",
+                "Dam" | "Dam." | "Dmg" | "Dmg." => "Damage",
+                "Atk" | "Atk." | "Att." => "Attack",
+                "Canc." => "Cancel",
+                "Prot." | "Protec." | "Protect." => "Protection",
+                "Rec." | "Recov." => "Recover",
+                "Pow/dam" => "Power&Damage",
+                "Counter-attack" => "Counter-Attack",
+                "Tie-break" => "Tie-Break",
+                "Modif." | "Mod." | "Mod" | "Left" | "" => continue,
+                "And" | "&" => {
+                    join = true;
+                    continue;
+                }
+                _ => word,
+            };
+
+            match tokens.last_mut() {
+                Some(last) if join => *last = format!("{}&{}", last, word),
+                Some(last) if (last == "+" || last == "-") && word.parse::<u32>().is_ok() => {
+                    last.push_str(word)
+                }
+                _ => tokens.push(word.to_string()),
+            }
+            join = false;
+        }
+
+        // `Power +2` is `+2 Power`, `Poison 2` is `2 Poison`
+        let number = tokens
+            .iter()
+            .position(|t| t.starts_with(['+', '-']) && t.len() > 1);
+        if let Some(i) = number {
+            let n = tokens.remove(i);
+            tokens.insert(0, n);
+        } else if tokens.len() > 1
+            && (STATUSES.contains(&tokens[0].as_str()) || tokens[0] == "Recover")
+            && tokens[1].parse::<u32>().is_ok()
+        {
+            tokens.swap(0, 1);
+        }
+
+        // `Power Exchange` is `Exchange Power`
+        if tokens.len() == 2 && ["Exchange", "Impose", "Protection"].contains(&tokens[1].as_str()) {
+            tokens.swap(0, 1);
+        }
+
+        let mut opp = false;
+        let keep_opp = match tokens.first().map(String::as_str) {
+            Some(t) if t.starts_with('+') => true,
+            Some(t) if t.starts_with('-') => false,
+            _ => false,
+        };
+        for i in (0..tokens.len()).rev() {
+            let per = i > 0 && tokens[i - 1] == "Per";
+            if tokens[i] == "Opp" && !(keep_opp || per) {
+                opp = true;
+                tokens.remove(i);
+            }
+        }
+
+        (tokens.join(" "), opp)
+    }
+}
+
+/// A compiled ability with the normalized text it was compiled from, in the
+/// layout of `compiled.json`.
+#[derive(Debug, Clone, Serialize)]
+pub struct Compiled {
+    #[serde(rename = "ability")]
+    pub text: String,
+    #[serde(flatten)]
+    pub ability: Ability,
+}
+
+pub struct Compiler {
+    grammar: Grammar,
+}
+
+impl Compiler {
+    pub fn new(grammar: Grammar) -> Self {
+        Compiler { grammar }
+    }
+
+    pub fn load() -> Result<Self, AssetError> {
+        Grammar::load().map(Compiler::new)
+    }
+
+    /// Compiles the ability or bonus text of a card. `kind` is
+    /// `AbilityType::Ability` or `AbilityType::Bonus`.
+    pub fn compile(&self, raw: &str, kind: AbilityType) -> Result<Compiled, CompileError> {
+        let normalized = Normalized::new(raw);
+        if !self.grammar.accepts(&normalized.text) {
+            return Err(CompileError::Grammar(normalized.text));
+        }
+        if normalized.conditions.len() > 3 {
+            return Err(CompileError::Conditions(normalized.conditions.len()));
+        }
+
+        let has = |name: &str| normalized.conditions.iter().any(|c| c == name);
+        let tokens: Vec<&str> = normalized.text.split(' ').collect();
+        let effect = Effect::new(&tokens, normalized.opp);
+
+        // Per-clan and per-round conditions multiply the effect
+        let per = [
+            ("Support", Per::Support),
+            ("Brawl", Per::Brawl),
+            ("Growth", Per::Growth),
+            ("Degrowth", Per::Degrowth),
+            ("Equalizer", Per::Equalizer),
+        ]
+        .into_iter()
+        .find(|(name, _)| has(name))
+        .map(|(_, per)| per);
+        let lost = has("Defeat") || has("Victory Or Defeat");
+        // Backlash turns the effect on the player
+        let backlash = has("Backlash");
+
+        let mut modifiers = ArrayVec::new();
+        let mut delayed = false;
+        let mut ability_type = kind;
+        match effect {
+            Effect::Basic(basics) => {
+                for mut basic in basics {
+                    if basic.per.is_none() {
+                        basic.per = per;
+                    }
+                    if lost || has("Reanimate") {
+                        basic.win = Some(false);
+                    }
+                    if backlash {
+                        basic.opp = false;
+                    }
+                    modifiers.push(Some(Modifier::Basic(basic)));
+                }
+                if tokens.contains(&"Round") {
+                    ability_type = AbilityType::Global;
+                }
+            }
+            Effect::Status(basics, delay) => {
+                for mut basic in basics {
+                    basic.per = per;
+                    if lost {
+                        basic.win = Some(false);
+                    }
+                    if backlash {
+                        basic.opp = false;
+                    }
+                    modifiers.push(Some(Modifier::Basic(basic)));
+                }
+                delayed = delay;
+                ability_type = match kind {
+                    AbilityType::Bonus => AbilityType::GlobalBonus,
+                    _ => AbilityType::GlobalAbility,
+                };
+            }
+            Effect::Other(others) => {
+                for mut modifier in others {
+                    if let Modifier::Status(status) = &mut modifier {
+                        if lost {
+                            status.win = Some(false);
+                        }
+                        if backlash {
+                            status.opp = false;
+                        }
+                    }
+                    modifiers.push(Some(modifier));
+                }
+                if LEADERS.contains(&normalized.text.as_str()) {
+                    ability_type = AbilityType::Global;
+                }
+            }
+            Effect::None => (),
+        }
+        if has("Team") {
+            ability_type = AbilityType::Global;
+        }
+
+        // `Normalized` only keeps the names of known conditions
+        let mut conditions = ArrayVec::new();
+        for condition in normalized.conditions.iter() {
+            conditions.push(Condition::from_name(condition));
+        }
+
+        Ok(Compiled {
+            text: normalized.text,
+            ability: Ability {
+                ability_type,
+                modifiers,
+                conditions,
+                delayed,
+                won: false,
+                remove: false,
+            },
+        })
+    }
+
+    /// Compiles the ability and bonus of every card in `data.json`.
+    pub fn compile_cards(&self) -> Result<Report, AssetError> {
+        let mut bytes = assets::read(assets::CARDS_FILE)?;
+        let cards: Vec<CardData> = simd_json::from_slice(&mut bytes)
+            .map_err(|e| AssetError::invalid(assets::CARDS_FILE, e))?;
+
+        let mut report = Report::default();
+        for card in cards.iter() {
+            for (id, raw, kind) in [
+                (card.ability_id, &card.ability, AbilityType::Ability),
+                (card.bonus_id, &card.bonus, AbilityType::Bonus),
+            ] {
+                if report.abilities.contains_key(&id) || report.failed.contains_key(&id) {
+                    continue;
+                }
+                match self.compile(raw, kind) {
+                    Ok(compiled) => {
+                        report.abilities.insert(id, compiled);
+                    }
+                    Err(e) => {
+                        report.failed.insert(id, (raw.clone(), e));
+                    }
+                }
+            }
+        }
+        Ok(report)
+    }
+}
+
+/// What an ability does, read from its normalized text.
+enum Effect {
+    Basic(Vec<BasicModifier>),
+    /// A status effect, and whether it waits a round before applying.
+    Status(Vec<BasicModifier>, bool),
+    Other(Vec<Modifier>),
+    /// Abilities the engine doesn't apply through modifiers.
+    None,
+}
+
+impl Effect {
+    fn new(tokens: &[&str], opp: bool) -> Effect {
+        let number = |t: &str| t.trim_start_matches('+').parse::<i32>().ok();
+        let bound = |tokens: &[&str], name: &str| {
+            tokens
+                .iter()
+                .position(|t| *t == name)
+                .and_then(|i| tokens.get(i + 1))
+                .and_then(|t| number(t))
+        };
+
+        match tokens {
+            [first, rest @ ..] if first.starts_with(['+', '-']) => {
+                let change = number(first).unwrap();
+                let min = bound(rest, "Min").unwrap_or(MIN);
+                let max = bound(rest, "Max").unwrap_or(MAX);
+                let per = match rest.iter().position(|t| *t == "Per") {
+                    Some(i) => match &rest[i + 1..] {
+                        ["Power", ..] => Some(Per::Power),
+                        ["Damage", ..] => Some(Per::Damage),
+                        ["Life", "Lost", ..] => Some(Per::LifeLost),
+                        ["Pillz", "Lost", ..] => Some(Per::PillzLost),
+                        ["Life", ..] => Some(Per::Life),
+                        ["Pillz", ..] => Some(Per::Pillz),
+                        ["Opp", "Power", ..] => Some(Per::OppPower),
+                        ["Opp", "Damage", ..] => Some(Per::OppDamage),
+                        ["Opp", "Life", ..] => Some(Per::OppLife),
+                        ["Opp", "Pillz", ..] => Some(Per::OppPillz),
+                        // Global abilities apply every round
+                        ["Round", ..] => None,
+                        // Multiplying by 1 would run the effect wrong, so
+                        // it isn't applied at all and `coverage` reports it
+                        _ => return Effect::None,
+                    },
+                    None => None,
+                };
+
+                let (targets, both) = match rest {
+                    ["Opp", stat, ..] => (*stat, Some(true)),
+                    ["Players" | "Cards" | "Xantiax", stat, ..] => (*stat, None),
+                    // A penalty without a target is on the opponent
+                    [stat, ..] => (*stat, Some(opp || change < 0)),
+                    [] => return Effect::None,
+                };
+                let Some(stats) = targets
+                    .split('&')
+                    .map(Effect::stat)
+                    .collect::<Option<Vec<_>>>()
+                else {
+                    return Effect::None;
+                };
+
+                let mut basics = Vec::new();
+                for stat in stats {
+                    for opp in both.map_or(vec![true, false], |opp| vec![opp]) {
+                        // Per Round abilities apply whether the card wins or not
+                        let end = matches!(stat, Stat::Life | Stat::Pillz);
+                        let each_round = rest.contains(&"Round");
+                        basics.push(BasicModifier {
+                            event_time: Effect::event_time(stat),
+                            win: Some(end && !each_round && rest[0] != "Xantiax"),
+                            change,
+                            per,
+                            stat,
+                            opp,
+                            min,
+                            max,
+                            always: false,
+                        });
+                    }
+                }
+                Effect::Basic(basics)
+            }
+            [n, "Recover", .., out_of] => Effect::Other(vec![Modifier::Recover(RecoverModifier {
+                event_time: EventTime::END,
+                win: None,
+                n: n.parse().unwrap(),
+                out_of: out_of.parse().unwrap(),
+            })]),
+            [n, kind @ ("Corrosion" | "Corrupt" | "Repair" | "Rebirth"), _, limit] => {
+                let kind = match *kind {
+                    "Corrosion" => StatusKind::Corrosion,
+                    "Corrupt" => StatusKind::Corrupt,
+                    "Repair" => StatusKind::Repair,
+                    _ => StatusKind::Rebirth,
+                };
+                Effect::Other(vec![Modifier::Status(StatusModifier {
+                    event_time: EventTime::END,
+                    win: Some(true),
+                    status: StatusEffect {
+                        kind,
+                        amount: n.parse().unwrap(),
+                        limit: limit.parse().unwrap(),
+                        ticks: 0,
+                    },
+                    opp: kind.harmful(),
+                })])
+            }
+            [n, status, bound, limit] => {
+                let n: i32 = n.parse().unwrap();
+                let limit: i32 = limit.parse().unwrap();
+                let (stats, sign, delayed): (&[Stat], i32, bool) = match *status {
+                    "Poison" => (&[Stat::Life], -1, true),
+                    "Toxin" => (&[Stat::Life], -1, false),
+                    "Heal" => (&[Stat::Life], 1, true),
+                    "Regen" => (&[Stat::Life], 1, false),
+                    "Consume" => (&[Stat::Pillz], -1, false),
+                    "Dope" => (&[Stat::Pillz], 1, false),
+                    "Mindwipe" => (&[Stat::Life, Stat::Pillz], -1, false),
+                    "Combust" => (&[Stat::Life, Stat::Pillz], -1, true),
+                    _ => return Effect::None,
+                };
+                let basics = stats
+                    .iter()
+                    .map(|&stat| BasicModifier {
+                        event_time: EventTime::END,
+                        win: Some(true),
+                        change: sign * n,
+                        per: None,
+                        stat,
+                        opp: sign < 0,
+                        min: if *bound == "Min" { limit } else { MIN },
+                        max: if *bound == "Max" { limit } else { MAX },
+                        always: true,
+                    })
+                    .collect();
+                Effect::Status(basics, delayed)
+            }
+            ["Cancel", "Leader"] => {
+                Effect::Other(vec![Modifier::CancelLeader(CancelLeaderModifier {
+                    event_time: EventTime::START,
+                    win: None,
+                    opp: true,
+                })])
+            }
+            ["Tune", "Out"] => Effect::Other(vec![Modifier::TuneOut(TuneOutModifier {
+                event_time: EventTime::PRE3,
+                win: None,
+                both: false,
+            })]),
+            ["Stop", target] => Effect::cancel(&[target]),
+            ["Cancel", target] => Effect::cancel(&target.split('&').collect::<Vec<_>>()),
+            ["Copy", target] => {
+                let copies: Vec<(Copy, EventTime)> = target
+                    .split('&')
+                    .filter_map(|t| match t {
+                        "Power" => Some((Copy::Power, EventTime::PRE3)),
+                        "Damage" => Some((Copy::Damage, EventTime::PRE3)),
+                        "Ability" => Some((Copy::Ability, EventTime::START)),
+                        "Bonus" => Some((Copy::Bonus, EventTime::START)),
+                        _ => None,
+                    })
+                    .collect();
+                Effect::Other(
+                    copies
+                        .into_iter()
+                        .map(|(copy, event_time)| {
+                            Modifier::Copy(CopyModifier {
+                                event_time,
+                                win: None,
+                                copy,
+                            })
+                        })
+                        .collect(),
+                )
+            }
+            ["Infiltrated"] => Effect::Other(vec![Modifier::Copy(CopyModifier {
+                event_time: EventTime::START,
+                win: None,
+                copy: Copy::Infiltrate,
+            })]),
+            ["Protection", rest @ ..] => {
+                let (both, target) = match rest {
+                    ["Cards", target] => (true, *target),
+                    [target] => (false, *target),
+                    _ => return Effect::None,
+                };
+                Effect::Other(
+                    target
+                        .split('&')
+                        .filter_map(|t| match t {
+                            "Power" => Some(Protect::Power),
+                            "Damage" => Some(Protect::Damage),
+                            "Attack" => Some(Protect::Attack),
+                            "Ability" => Some(Protect::Ability),
+                            "Bonus" => Some(Protect::Bonus),
+                            _ => None,
+                        })
+                        .map(|prot| {
+                            Modifier::Protection(ProtectionModifier {
+                                event_time: EventTime::PRE3,
+                                win: None,
+                                prot,
+                                both,
+                            })
+                        })
+                        .collect(),
+                )
+            }
+            [verb @ ("Exchange" | "Impose"), target] => Effect::Other(
+                target
+                    .split('&')
+                    .filter_map(|t| match (*verb, t) {
+                        ("Exchange", "Power") => Some(Exchange::Power),
+                        ("Exchange", "Damage") => Some(Exchange::Damage),
+                        ("Impose", "Power") => Some(Exchange::ImposePower),
+                        ("Impose", "Damage") => Some(Exchange::ImposeDamage),
+                        _ => None,
+                    })
+                    .map(|ex| {
+                        Modifier::Exchange(ExchangeModifier {
+                            event_time: EventTime::PRE2,
+                            win: None,
+                            ex,
+                        })
+                    })
+                    .collect(),
+            ),
+            _ => {
+                let keyword = match tokens.join(" ").as_str() {
+                    "Counter-Attack" => Keyword::CounterAttack,
+                    "Tie-Break" => Keyword::TieBreak,
+                    "Bypass" => Keyword::Bypass,
+                    "Remove Ability Conditions" => Keyword::RemoveConditions,
+                    "Illusion" => Keyword::Illusion,
+                    "Hazard" => Keyword::Hazard,
+                    "Limitless" => Keyword::Limitless,
+                    "Overdose" => Keyword::Overdose,
+                    "Beyond" => Keyword::Beyond,
+                    "Perfection" => Keyword::Perfection,
+                    "Sinister Symmetry" => Keyword::SinisterSymmetry,
+                    _ => return Effect::Other(Vec::new()),
+                };
+                Effect::Other(vec![Modifier::Keyword(KeywordModifier {
+                    event_time: keyword.event_time(),
+                    win: None,
+                    keyword,
+                })])
+            }
+        }
+    }
+
+    fn cancel(targets: &[&str]) -> Effect {
+        Effect::Other(
+            targets
+                .iter()
+                .filter_map(|t| match *t {
+                    "Power" => Some(Cancel::Power),
+                    "Damage" => Some(Cancel::Damage),
+                    "Attack" => Some(Cancel::Attack),
+                    "Ability" => Some(Cancel::Ability),
+                    "Bonus" => Some(Cancel::Bonus),
+                    "Pillz" => Some(Cancel::Pillz),
+                    "Life" => Some(Cancel::Life),
+                    _ => None,
+                })
+                .map(|cancel| {
+                    Modifier::Cancel(CancelModifier {
+                        event_time: EventTime::PRE4,
+                        win: None,
+                        cancel,
+                        applied: None,
+                    })
+                })
+                .collect(),
+        )
+    }
+
+    fn stat(name: &str) -> Option<Stat> {
+        match name {
+            "Power" => Some(Stat::Power),
+            "Damage" => Some(Stat::Damage),
+            "Attack" => Some(Stat::Attack),
+            "Life" => Some(Stat::Life),
+            "Pillz" => Some(Stat::Pillz),
+            _ => None,
+        }
+    }
+
+    fn event_time(stat: Stat) -> EventTime {
+        match stat {
+            Stat::Power | Stat::Damage => EventTime::PRE2,
+            Stat::Attack => EventTime::POST1,
+            Stat::Life | Stat::Pillz => EventTime::END,
+        }
+    }
+}
+
+/// The result of compiling every card: the abilities by id, and the
+/// strings that couldn't be compiled.
+#[derive(Debug, Default)]
+pub struct Report {
+    pub abilities: BTreeMap<u32, Compiled>,
+    pub failed: BTreeMap<u32, (String, CompileError)>,
+}
+
+impl Report {
+    /// Writes the abilities in the `compiled.json` layout.
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        let file = File::create(path)?;
+        serde_json::to_writer(file, &self.abilities).map_err(io::Error::from)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Compiler, Normalized};
+    use crate::{
+        ability::{AbilityType, ABILITIES},
+        modifiers::{Modifier, Per},
+    };
+
+    #[test]
+    fn normalizes() {
+        let n = Normalized::new("Night: Vict. Or Def.: -1 Opp. Pow And Dmg,min 3");
+        assert_eq!(n.conditions, ["Night", "Victory Or Defeat"]);
+        assert_eq!(n.text, "-1 Power&Damage Min 3");
+        assert!(n.opp);
+
+        let n = Normalized::new("Versus [clan:4][clan:3]: Power +2");
+        assert_eq!(n.conditions, ["Versus [Clan:4][Clan:3]"]);
+        assert_eq!(n.text, "+2 Power");
+        assert!(!n.opp);
+    }
+
+    #[test]
+    fn compiles_per_lost() {
+        let compiler = Compiler::load().unwrap();
+        let per = |raw: &str| {
+            let compiled = compiler.compile(raw, AbilityType::Ability).unwrap();
+            match compiled.ability.modifiers[0] {
+                Some(Modifier::Basic(basic)) => basic.per,
+                _ => None,
+            }
+        };
+        assert_eq!(per("+1 Power Per Life Lost Max. 14"), Some(Per::LifeLost));
+        assert_eq!(
+            per("-1 Opp Power Per Pillz Lost, Min 5"),
+            Some(Per::PillzLost)
+        );
+        assert_eq!(per("+1 Power Per Life Left Max. 13"), Some(Per::Life));
+    }
+
+    #[test]
+    fn matches_compiled_json() {
+        let report = Compiler::load().unwrap().compile_cards().unwrap();
+        assert!(report.failed.is_empty(), "{:?}", report.failed);
+        assert_eq!(report.abilities.len(), ABILITIES.len());
+        for (id, compiled) in report.abilities.iter() {
+            assert_eq!(ABILITIES[id], compiled.ability, "#{} {}", id, compiled.text);
+        }
+    }
+}
@@ -0,0 +1,216 @@
+use std::{
+    collections::BTreeMap,
+    fmt::{Display, Formatter, Result},
+};
+
+use serde::Serialize;
+
+use crate::{
+    ability::{load_abilities, Abilities, Ability, Condition},
+    assets::AssetError,
+    card::{load_cards, BaseCard},
+    modifiers::{Modifier, Per},
+};
+
+/// Why an ability isn't simulated faithfully.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub enum Gap {
+    /// The ability has no modifiers, or isn't compiled at all, so it does
+    /// nothing.
+    NoModifiers,
+    /// A condition read as `Condition::None`, which is always met.
+    UnknownCondition,
+    /// A modifier multiplies by a value the engine treats as 1.
+    UnhandledPer(Per),
+    /// The text multiplies by something, but no modifier does.
+    DroppedPer,
+}
+
+impl Gap {
+    /// The gaps of an ability with this text, none if the engine executes it
+    /// as written.
+    pub fn find(ability: Option<&Ability>, text: &str) -> Vec<Gap> {
+        let Some(ability) = ability.filter(|a| !a.modifiers.is_empty()) else {
+            return vec![Gap::NoModifiers];
+        };
+
+        let mut gaps = Vec::new();
+        if ability
+            .conditions
+            .iter()
+            .flatten()
+            .any(|c| *c == Condition::None)
+        {
+            gaps.push(Gap::UnknownCondition);
+        }
+        let mut multiplied = false;
+        for modifier in ability.modifiers.iter().flatten() {
+            if let Modifier::Basic(basic) = modifier {
+                match basic.per {
+                    Some(per) if !per.is_handled() => gaps.push(Gap::UnhandledPer(per)),
+                    _ => (),
+                }
+                multiplied |= basic.per.is_some();
+            }
+        }
+        // Per Round abilities are global ones instead
+        let words: Vec<&str> = text.split_whitespace().collect();
+        let per = words
+            .windows(2)
+            .any(|w| w[0].eq_ignore_ascii_case("per") && !w[1].starts_with("Round"));
+        if per && !multiplied {
+            gaps.push(Gap::DroppedPer);
+        }
+        gaps
+    }
+}
+
+impl Display for Gap {
+    fn fmt(&self, f: &mut Formatter) -> Result {
+        match self {
+            Gap::NoModifiers => write!(f, "no modifiers"),
+            Gap::UnknownCondition => write!(f, "unknown condition"),
+            Gap::UnhandledPer(per) => write!(f, "unhandled Per {:?}", per),
+            Gap::DroppedPer => write!(f, "Per dropped"),
+        }
+    }
+}
+
+/// An ability or bonus with gaps, and the cards that have it.
+#[derive(Debug, Clone, Serialize)]
+pub struct Uncovered {
+    pub id: u32,
+    pub text: String,
+    pub gaps: Vec<Gap>,
+    pub cards: Vec<String>,
+}
+
+/// The abilities of every card the engine can't simulate faithfully.
+#[derive(Debug, Default, Serialize)]
+pub struct Coverage {
+    /// Abilities and bonuses with gaps, the ones on the most cards first.
+    pub abilities: Vec<Uncovered>,
+    /// The affected cards by clan.
+    pub clans: BTreeMap<String, Vec<String>>,
+}
+
+impl Coverage {
+    pub fn new(cards: &[BaseCard], abilities: &Abilities) -> Self {
+        let mut uncovered: BTreeMap<u32, Uncovered> = BTreeMap::new();
+        let mut clans: BTreeMap<String, Vec<String>> = BTreeMap::new();
+
+        for card in cards {
+            let mut affected = false;
+            for (id, text) in [
+                (card.ability_id, &card.ability),
+                (card.bonus_id, &card.bonus),
+            ] {
+                if id == 0 {
+                    continue;
+                }
+                let gaps = Gap::find(abilities.get(&id), text);
+                if gaps.is_empty() {
+                    continue;
+                }
+
+                affected = true;
+                uncovered
+                    .entry(id)
+                    .or_insert_with(|| Uncovered {
+                        id,
+                        text: text.clone(),
+                        gaps,
+                        cards: Vec::new(),
+                    })
+                    .cards
+                    .push(card.name.clone());
+            }
+            if affected {
+                clans
+                    .entry(card.clan.to_string())
+                    .or_default()
+                    .push(card.name.clone());
+            }
+        }
+
+        let mut abilities: Vec<Uncovered> = uncovered.into_values().collect();
+        abilities.sort_by(|a, b| b.cards.len().cmp(&a.cards.len()).then(a.id.cmp(&b.id)));
+        Coverage { abilities, clans }
+    }
+
+    /// The coverage of the cards and abilities in the asset directory.
+    pub fn load() -> std::result::Result<Self, AssetError> {
+        Ok(Coverage::new(load_cards()?, load_abilities()?))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.abilities.is_empty()
+    }
+}
+
+impl Display for Coverage {
+    fn fmt(&self, f: &mut Formatter) -> Result {
+        for uncovered in self.abilities.iter() {
+            let gaps: Vec<String> = uncovered.gaps.iter().map(Gap::to_string).collect();
+            writeln!(
+                f,
+                "#{} {:?}: {} ({} cards)",
+                uncovered.id,
+                uncovered.text,
+                gaps.join(", "),
+                uncovered.cards.len()
+            )?;
+            writeln!(f, "  {}", uncovered.cards.join(", "))?;
+        }
+        for (clan, cards) in self.clans.iter() {
+            writeln!(f, "{}: {} cards", clan, cards.len())?;
+            writeln!(f, "  {}", cards.join(", "))?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Coverage, Gap};
+    use crate::{
+        ability::{Condition, ABILITIES},
+        card::load_cards,
+        modifiers::Modifier,
+    };
+
+    #[test]
+    fn finds_gaps() {
+        let cards = load_cards().unwrap();
+        let card = cards.iter().find(|c| c.name == "Solomon").unwrap();
+
+        let mut abilities = (**ABILITIES).clone();
+        abilities
+            .get_mut(&card.ability_id)
+            .unwrap()
+            .modifiers
+            .clear();
+        let coverage = Coverage::new(cards, &abilities);
+        assert_eq!(coverage.abilities.len(), 1, "{}", coverage);
+        assert_eq!(coverage.abilities[0].gaps, [Gap::NoModifiers]);
+        assert_eq!(coverage.abilities[0].cards, ["Solomon"]);
+        assert_eq!(coverage.clans["Leader"], ["Solomon"]);
+
+        let mut ability = ABILITIES[&card.ability_id];
+        ability.conditions.push(Some(Condition::None));
+        assert_eq!(
+            Gap::find(Some(&ability), &card.ability),
+            [Gap::UnknownCondition]
+        );
+
+        // A multiplier the compiler left out
+        let card = cards.iter().find(|c| c.name == "Kiki I").unwrap();
+        let mut ability = ABILITIES[&card.ability_id];
+        assert!(Gap::find(Some(&ability), &card.ability).is_empty());
+        if let Some(Modifier::Basic(basic)) = &mut ability.modifiers[0] {
+            basic.per = None;
+        }
+        assert_eq!(Gap::find(Some(&ability), &card.ability), [Gap::DroppedPer]);
+        assert!(Coverage::new(cards, &ABILITIES).is_empty());
+    }
+}
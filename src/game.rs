@@ -1,50 +1,57 @@
 use std::{
     cell::RefCell,
+    collections::hash_map::DefaultHasher,
     fmt::Display,
-    hash::Hash,
-    sync::atomic::{AtomicU32, Ordering},
+    hash::{Hash, Hasher},
 };
 
 use colored::{ColoredString, Colorize};
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 
 use crate::{
     ability::AbilityType,
-    battle::{BattleData, Events},
-    card::Hand,
-    modifiers::EventTime,
+    battle::{BattleData, BattleEvent, BattleLog, Events, Statuses},
+    card::{Card, Hand},
+    modifiers::{EventTime, Keyword},
+    output::{Output, Terminal},
     types::Clan,
+    utils::Clamp,
 };
 
-pub static mut PRINT: bool = true;
-macro_rules! println {
-    ($($rest:tt)*) => {
-        unsafe {
-            if PRINT {
-                std::println!($($rest)*)
-            }
-        }
-    }
-}
-
-pub static mut BATTLE_COUNT: AtomicU32 = AtomicU32::new(0);
+/// Most pillz a player can hold. The solvers' range tables stop there.
+pub const MAX_PILLZ: u8 = 31;
 
-#[derive(Debug, Clone, Copy, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, Hash)]
 pub enum RoundWin {
     WIN,
     LOSE,
     NONE,
 }
 
-#[derive(Debug, Clone, Copy)]
+fn twelve() -> u8 {
+    12
+}
+
+/// Life and pillz stay `u8`. Changes to them go through `set_life` and
+/// `set_pillz`, or `clamp_life` and `clamp_pillz`, which clamp instead of
+/// wrapping.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
 pub struct Player {
     pub player_type: PlayerType,
     pub life: u8,
     pub life_previous: u8,
     pub pillz: u8,
     pub pillz_previous: u8,
+    /// Life and pillz the game started with, that `Per::LifeLost` and
+    /// `Per::PillzLost` count from.
+    #[serde(default = "twelve")]
+    pub life_start: u8,
+    #[serde(default = "twelve")]
+    pub pillz_start: u8,
     pub won: RoundWin,
     pub won_previous: RoundWin,
+    #[serde(default)]
+    pub statuses: Statuses,
 }
 
 impl Player {
@@ -55,10 +62,32 @@ impl Player {
             life_previous: 12,
             pillz: 12,
             pillz_previous: 12,
+            life_start: 12,
+            pillz_start: 12,
             won: RoundWin::NONE,
             won_previous: RoundWin::NONE,
+            statuses: Statuses::default(),
         }
     }
+    /// Life in the range it's stored in.
+    pub fn clamp_life(life: i32) -> u8 {
+        u8::clamp_from(life)
+    }
+    /// Pillz in `0..=MAX_PILLZ`, so the solvers' range tables cover them.
+    pub fn clamp_pillz(pillz: i32) -> u8 {
+        u8::clamp_from(pillz).min(MAX_PILLZ)
+    }
+    pub fn set_life(&mut self, life: i32) {
+        self.life = Player::clamp_life(life);
+    }
+    pub fn set_pillz(&mut self, pillz: i32) {
+        self.pillz = Player::clamp_pillz(pillz);
+    }
+    /// Starts the game with `life` and `pillz` instead of 12.
+    pub fn start_with(&mut self, life: u8, pillz: u8) {
+        (self.life, self.life_previous, self.life_start) = (life, life, life);
+        (self.pillz, self.pillz_previous, self.pillz_start) = (pillz, pillz, pillz);
+    }
     fn name(&self) -> ColoredString {
         if self.player_type == PlayerType::Player {
             " Player ".bright_white().on_cyan()
@@ -119,7 +148,7 @@ impl Player {
             // )
         );
         // print!("{:<3} ", (-(pillz_used as i32)).to_string().bright_black());
-        println!(
+        print!(
             "{}",
             format!(
                 "{:<w1$}{}{}",
@@ -131,10 +160,14 @@ impl Player {
             )
             .on_white()
         );
+        for effect in self.statuses.iter() {
+            print!("  {}", effect.to_string().bright_magenta());
+        }
+        println!();
     }
 }
 
-#[derive(Clone, Copy, Default, Debug, Eq, PartialEq, Deserialize, Hash)]
+#[derive(Clone, Copy, Default, Debug, Eq, PartialEq, Deserialize, Serialize, Hash)]
 pub struct Selection {
     pub index: usize,
     #[serde(default)]
@@ -196,7 +229,7 @@ impl Selection {
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
 pub enum GameStatus {
     Player,
     Opponent,
@@ -204,7 +237,7 @@ pub enum GameStatus {
     Playing,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
 pub enum PlayerType {
     Player,
     Opponent,
@@ -222,6 +255,9 @@ pub struct Game {
     pub events1: Events,
     pub events2: Events,
     pub flip: u8,
+    pub day: bool,
+    /// Where this game prints to. Solvers swap in `Silent`.
+    pub output: &'static dyn Output,
 }
 
 #[allow(dead_code)]
@@ -256,6 +292,8 @@ impl Game {
             events1: events1,
             events2: events2,
             flip: 0,
+            day: true,
+            output: &Terminal,
         }
     }
     pub fn random() -> Self {
@@ -307,15 +345,19 @@ impl Game {
         }
     }
 
+    #[inline]
     pub fn print_status(&self) {
-        unsafe {
-            if !PRINT {
-                return;
-            }
-        }
+        self.output.status(self);
+    }
 
+    pub fn render_status(&self) {
         match self.status() {
             GameStatus::Playing => {
+                if self.day {
+                    println!("{}", " Day ".black().on_bright_yellow());
+                } else {
+                    println!("{}", " Night ".bright_white().on_black());
+                }
                 self.p2.print();
                 self.h2.print(
                     self.s2
@@ -411,14 +453,28 @@ impl Game {
     pub fn has_someone_selected(&self) -> bool {
         self.s1.is_some() || self.s2.is_some()
     }
-
-    fn print_battle(&self, attack1: u8, attack2: u8) {
-        unsafe {
-            if !PRINT {
-                return;
-            }
+    /// Hash of everything the rest of the game depends on, so the same
+    /// position reached through different selections hashes the same. The
+    /// hands' cards aren't part of it, only what's left of them: keys only
+    /// compare positions of one game.
+    pub fn position_hash(&self) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        (self.round, self.flip, self.day, self.s1, self.s2).hash(&mut hasher);
+        for player in [&self.p1, &self.p2] {
+            player.life.hash(&mut hasher);
+            player.pillz.hash(&mut hasher);
+            player.won.hash(&mut hasher);
+            player.statuses.hash(&mut hasher);
+        }
+        for card in self.h1.cards.iter().chain(self.h2.cards.iter()) {
+            (card.played, card.won).hash(&mut hasher);
         }
+        self.events1.hash(&mut hasher);
+        self.events2.hash(&mut hasher);
+        hasher.finish()
+    }
 
+    pub fn render_battle(&self, attack1: u16, attack2: u16) {
         match (self.s1, self.s2) {
             (Some(s1), Some(s2)) => {
                 // if let Some(s1) = self.s1 && let Some(s2) = self.s2 {
@@ -477,7 +533,7 @@ impl Game {
         }
     }
 
-    fn battle(&mut self) {
+    fn battle(&mut self) -> BattleLog {
         let s1 = &mut self.s1.unwrap();
         let s2 = &mut self.s2.unwrap();
 
@@ -510,15 +566,56 @@ impl Game {
         self.p1.pillz_previous = self.p1.pillz;
         self.p2.pillz_previous = self.p2.pillz;
 
+        let mut log = BattleLog::new(self.output.logging());
         {
             let card1 = &self.h1[s1.index];
             let card2 = &self.h2[s2.index];
 
-            self.events1.add(card1.get_ability());
-            self.events2.add(card2.get_ability());
-            self.events1.add(card1.get_bonus());
-            self.events2.add(card2.get_bonus());
+            // Night bonuses only exist while it is night
+            let bonus1 = !self.day || !card1.has_night_bonus();
+            let bonus2 = !self.day || !card2.has_night_bonus();
+
+            for (player, ability_id, ability, active) in [
+                (
+                    PlayerType::Player,
+                    card1.ability_id,
+                    card1.get_ability(),
+                    true,
+                ),
+                (
+                    PlayerType::Opponent,
+                    card2.ability_id,
+                    card2.get_ability(),
+                    true,
+                ),
+                (
+                    PlayerType::Player,
+                    card1.bonus_id,
+                    card1.get_bonus(),
+                    bonus1,
+                ),
+                (
+                    PlayerType::Opponent,
+                    card2.bonus_id,
+                    card2.get_bonus(),
+                    bonus2,
+                ),
+            ] {
+                // Leader abilities are registered once, when the game starts
+                if !active || ability.ability_type == AbilityType::Global {
+                    continue;
+                }
+                let events = if player == PlayerType::Player {
+                    &mut self.events1
+                } else {
+                    &mut self.events2
+                };
+                if !events.add(ability) && ability_id != 0 {
+                    log.push(BattleEvent::AbilityDropped { player, ability_id });
+                }
+            }
         }
+        let log = RefCell::new(log);
 
         let Game {
             events1,
@@ -544,6 +641,7 @@ impl Game {
 
         let battle_data1 = BattleData {
             round: self.round,
+            day: self.day,
             first: first_turn == PlayerType::Player,
             hand: &h1,
             opp_hand: &h2,
@@ -554,9 +652,11 @@ impl Game {
             opp_card: card2,
             opp_pillz_used: total_pillz2,
             events: &events1,
+            log: &log,
         };
         let battle_data2 = BattleData {
             round: self.round,
+            day: self.day,
             first: first_turn == PlayerType::Opponent,
             hand: &h2,
             opp_hand: &h1,
@@ -567,6 +667,7 @@ impl Game {
             opp_card: &card1,
             opp_pillz_used: total_pillz1,
             events: &events2,
+            log: &log,
         };
 
         {
@@ -602,8 +703,17 @@ impl Game {
             card2.borrow_mut().damage.value += 2;
         }
 
-        let attack1 = (pillz1 + 1) * card1.borrow().power.value;
-        let attack2 = (pillz2 + 1) * card2.borrow().power.value;
+        // Overdose makes every pill count twice
+        let attack = |pillz: u8, card: &Card| {
+            let overdose = if card.keyword(Keyword::Overdose) {
+                pillz
+            } else {
+                0
+            };
+            (pillz as u16 + overdose as u16 + 1).saturating_mul(card.power.value)
+        };
+        let attack1 = attack(pillz1, &card1.borrow());
+        let attack2 = attack(pillz2, &card2.borrow());
 
         card1.borrow_mut().attack.value = attack1;
         card2.borrow_mut().attack.value = attack2;
@@ -618,24 +728,80 @@ impl Game {
             let mut p2 = p2.borrow_mut();
             let mut card1 = card1.borrow_mut();
             let mut card2 = card2.borrow_mut();
-            if attack1 > attack2
-                || (attack1 == attack2
-                    && (card1.level < card2.level
-                        || (card1.level == card2.level && first_turn == PlayerType::Player)))
-            {
-                p2.life -= card1.damage.value.min(p2.life);
+            let mut log = log.borrow_mut();
+            log.push(BattleEvent::Attack {
+                player: PlayerType::Player,
+                attack: attack1,
+            });
+            log.push(BattleEvent::Attack {
+                player: PlayerType::Opponent,
+                attack: attack2,
+            });
+            // Tie-Break wins ties before levels are compared
+            let tie_break1 = card1.keyword(Keyword::TieBreak);
+            let tie_break2 = card2.keyword(Keyword::TieBreak);
+            let won = if attack1 != attack2 {
+                attack1 > attack2
+            } else if tie_break1 != tie_break2 {
+                tie_break1
+            } else {
+                card1.level < card2.level
+                    || (card1.level == card2.level && first_turn == PlayerType::Player)
+            };
+            if won {
+                let hazard = card1.keyword(Keyword::Hazard) as i32;
+                let damage = Player::clamp_life(card1.damage.value as i32 + hazard).min(p2.life);
+                let life = p2.life as i32 - damage as i32;
+                p2.set_life(life);
                 card1.won = true;
                 p1.won = RoundWin::WIN;
                 p2.won = RoundWin::LOSE;
+                log.push(BattleEvent::RoundWon {
+                    player: PlayerType::Player,
+                });
+                log.push(BattleEvent::Damage {
+                    player: PlayerType::Opponent,
+                    amount: damage,
+                });
             } else {
-                p1.life -= card2.damage.value.min(p1.life);
+                let hazard = card2.keyword(Keyword::Hazard) as i32;
+                let damage = Player::clamp_life(card2.damage.value as i32 + hazard).min(p1.life);
+                let life = p1.life as i32 - damage as i32;
+                p1.set_life(life);
                 card2.won = true;
                 p2.won = RoundWin::WIN;
                 p1.won = RoundWin::LOSE;
+                log.push(BattleEvent::RoundWon {
+                    player: PlayerType::Opponent,
+                });
+                log.push(BattleEvent::Damage {
+                    player: PlayerType::Player,
+                    amount: damage,
+                });
             }
 
-            p1.pillz -= total_pillz1;
-            p2.pillz -= total_pillz2;
+            // Counter-Attack hits back when the card loses
+            for (card, winner, player) in [
+                (&card1, &mut p2, PlayerType::Opponent),
+                (&card2, &mut p1, PlayerType::Player),
+            ] {
+                if !card.won && card.keyword(Keyword::CounterAttack) && winner.life > 0 {
+                    let life = winner.life as i32 - 1;
+                    winner.set_life(life);
+                    log.push(BattleEvent::Damage { player, amount: 1 });
+                }
+            }
+
+            let pillz = (
+                p1.pillz as i32 - total_pillz1 as i32,
+                p2.pillz as i32 - total_pillz2 as i32,
+            );
+            p1.set_pillz(pillz.0);
+            p2.set_pillz(pillz.1);
+
+            // Effects added this round only tick from the next one
+            Statuses::tick(&mut p1, &mut log);
+            Statuses::tick(&mut p2, &mut log);
         }
 
         events1.borrow_mut().execute_end(&battle_data1);
@@ -644,14 +810,11 @@ impl Game {
         card1.borrow_mut().played = true;
         card2.borrow_mut().played = true;
 
-        self.print_battle(attack1, attack2);
+        self.output.battle(self, attack1, attack2);
 
         self.round += 1;
 
-        unsafe {
-            // *BATTLE_COUNT.get_mut() += 1;
-            BATTLE_COUNT.fetch_add(1, Ordering::Relaxed);
-        }
+        log.into_inner()
     }
 
     pub fn can_select(&self, index: usize, pillz: u8, fury: bool) -> bool {
@@ -666,39 +829,42 @@ impl Game {
         true
     }
 
-    pub fn select(&mut self, index: usize, pillz: u8, fury: bool) -> bool {
+    /// Selects a card for whoever's turn it is. Returns the battle log once
+    /// both players have selected and the round has been played.
+    pub fn select(&mut self, index: usize, pillz: u8, fury: bool) -> Option<BattleLog> {
         let s = Some(Selection { index, pillz, fury });
         if self.round % 2 == self.flip {
             if self.s1.is_some() {
                 self.s2 = s;
-                self.battle();
+                let log = self.battle();
                 self.s1 = None;
                 self.s2 = None;
-                true
+                Some(log)
             } else {
                 self.s1 = s;
                 self.print_status();
-                false
+                None
             }
         } else if self.s2.is_some() {
             self.s1 = s;
-            self.battle();
+            let log = self.battle();
             self.s1 = None;
             self.s2 = None;
-            true
+            Some(log)
         } else {
             self.s2 = s;
             self.print_status();
-            false
+            None
         }
     }
 
-    pub fn select_both(&mut self, s1: Selection, s2: Selection) {
+    pub fn select_both(&mut self, s1: Selection, s2: Selection) -> BattleLog {
         self.s1 = Some(s1);
         self.s2 = Some(s2);
-        self.battle();
+        let log = self.battle();
         self.s1 = None;
         self.s2 = None;
+        log
     }
 
     pub fn clear_selection(&mut self) {
@@ -1 +1,15 @@
+//! The battle engine and solvers, for use from other crates. Point them at
+//! the card data with `assets::set_dir` and check it with `assets::load`
+//! before building any hand.
+
+pub mod ability;
+pub mod assets;
+pub mod battle;
+pub mod card;
+pub mod game;
+pub mod modifiers;
+pub mod output;
+pub mod snapshot;
+pub mod solver;
+pub mod types;
 pub mod utils;
@@ -1,144 +1,66 @@
-use std::{
-    env,
-    io::{self, Result},
-};
+use std::{io::Result, process};
 
-use game::Selection;
+use clap::Parser;
 use rayon::ThreadPoolBuilder;
 
 use crate::{
-    card::Hand,
-    game::{Game, GameStatus, PlayerType},
-    solver::{SelectionResult, Solver},
+    cli::{Cli, Command},
+    replay::Replay,
+    solver::Options,
 };
+use urban_recreation_rust::{ability, assets, battle, card, game, modifiers, snapshot, solver};
 
-mod ability;
-mod battle;
-mod card;
-mod game;
-mod modifiers;
+mod cli;
+mod compiler;
+mod coverage;
+mod replay;
 mod server;
-mod solver;
-mod solver_2;
 mod testcases;
-mod types;
-pub mod utils;
 
-#[allow(unreachable_code)]
 #[actix_web::main]
 async fn main() -> Result<()> {
-    ThreadPoolBuilder::new()
-        .num_threads(4)
-        .build_global()
-        .unwrap();
-    let args: Vec<String> = env::args().collect();
-    let h1: Hand;
-    let h2: Hand;
-    let mut flip = 0u8;
-    if args.len() >= 9 {
-        h1 = Hand::from_names(
-            args[1].as_str(),
-            args[2].as_str(),
-            args[3].as_str(),
-            args[4].as_str(),
-        );
-        h2 = Hand::from_names(
-            args[5].as_str(),
-            args[6].as_str(),
-            args[7].as_str(),
-            args[8].as_str(),
-        );
-        if args.len() == 10 {
-            flip = 1;
-        }
-    } else {
-        // h1 = Hand::from_names("Anagone", "Doela", "Elios", "Galahad");
-        // h2 = Hand::from_names("Murray", "Petra", "Buck", "Keile");
-
-        server::serve().await?;
-
-        return Ok(());
+    let cli = Cli::parse();
+    if let Some(threads) = cli.threads {
+        ThreadPoolBuilder::new()
+            .num_threads(threads)
+            .build_global()
+            .unwrap();
     }
-    let mut game = Game::new(h1, h2);
-    game.flip = flip;
-
-    game.print_status();
-
-    if flip == 0 {
-        // let best = Solver::solve(&game);
-
-        // match (best, game.get_turn()) {
-        //     (SelectionResult::Player(_), PlayerType::Opponent)
-        //     | (SelectionResult::Opponent(_), PlayerType::Player) => {
-        //         Solver::middle(&game);
-        //     }
-        //     (_, _) => println!("{:?}", best),
-        // }
-        Solver::middle(&game);
+    if let Some(dir) = cli.assets {
+        assets::set_dir(dir);
     }
-
-    // return Ok(());
-
-    println!("{} turn", game.get_turn_name());
-    for line in io::stdin().lines() {
-        let mut input = line.unwrap();
-
-        let cancelled: bool;
-        if input.as_str() == "cancel" {
-            game.clear_selection();
-            game.print_status();
-            // cancelled = true;
-            continue;
-        } else if input.starts_with("x ") {
-            input = input[2..].to_string();
-            game.clear_selection();
-            cancelled = true;
-        } else {
-            cancelled = false;
-        }
-
-        let selected = Selection::parse(input);
-        if selected.is_none() {
-            continue;
-        }
-
-        let Selection { index, pillz, fury } = selected.unwrap();
-
-        // println!("{}, {}, {}", index, pillz, fury);
-        if !game.can_select(index, pillz, fury) {
-            continue;
+    // Compiling writes compiled.json, so it mustn't need it
+    if !matches!(cli.command, Command::Compile { .. }) {
+        if let Err(e) = assets::load() {
+            eprintln!("{}", e);
+            process::exit(1);
         }
+    }
 
-        let battled = game.select(index, pillz, fury);
-        // if !battled {
-        //     game.print_status();
-        // }
-        if game.status() != GameStatus::Playing {
-            break;
-        }
-
-        let turn = game.get_turn();
-
-        if game.round == 0 {
-            if !cancelled && turn == PlayerType::Player {
-                Solver::middle(&game);
-            }
-        } else {
-            let best = Solver::solve(&game);
-
-            match (best, turn) {
-                (SelectionResult::Player(_), PlayerType::Opponent)
-                | (SelectionResult::Opponent(_), PlayerType::Player) => {
-                    Solver::middle(&game);
-                }
-                (_, _) => println!("{}", best),
-            }
-        }
+    let options = Options {
+        eval: cli.eval,
+        ..Options::new(cli.solver)
+    };
+    let result = match cli.command {
+        Command::Play(args) => args.game().map(|game| cli::play(game, &options)),
+        Command::Solve { game, moves, mixed } => game
+            .game()
+            .and_then(|game| cli::solve(game, &moves, mixed, &options)),
+        Command::Serve { host, port } => return server::serve(&host, port, options).await,
+        Command::Replay { file } => Replay::load(&file)
+            .and_then(|replay| replay.run(&options))
+            .map(|_| ())
+            .map_err(|e| e.to_string()),
+        Command::Card { name } => cli::card(&name),
+        Command::Simulate(args) => args.game().map(|game| cli::simulate(game, &options)),
+        Command::Compile { output } => cli::compile(output),
+        Command::Coverage { json } => cli::coverage(json),
+    };
 
-        println!("{} turn", game.get_turn_name());
+    if let Err(e) = result {
+        eprintln!("{}", e);
+        process::exit(1);
     }
-    game.print_status();
-
     Ok(())
 }
 
@@ -169,7 +91,7 @@ async fn main() -> Result<()> {
 mod test1 {
     use regex::{Captures, Regex};
 
-    use crate::{ability::ABILITIES, card::CARD_IDS, types::Clan};
+    use urban_recreation_rust::{ability::ABILITIES, card::CARD_IDS, types::Clan};
 
     #[test]
     fn test() {
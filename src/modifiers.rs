@@ -1,24 +1,16 @@
-use serde::Deserialize;
-use serde_repr::Deserialize_repr;
+use serde::{Deserialize, Serialize};
+use serde_repr::{Deserialize_repr, Serialize_repr};
 
 use crate::{
     ability::{Ability, AbilityType},
-    battle::BattleData,
+    battle::{BattleData, BattleEvent, StatusEffect},
+    card::CardAttr,
+    game::Player,
     types::Clan,
+    utils::Clamp,
 };
 
-pub static mut PRINT: bool = true;
-macro_rules! println {
-    ($($rest:tt)*) => {
-        unsafe {
-            if PRINT {
-                std::println!($($rest)*)
-            }
-        }
-    }
-}
-
-#[derive(Debug, Clone, Copy, Deserialize_repr, PartialEq)]
+#[derive(Debug, Clone, Copy, Serialize_repr, Deserialize_repr, PartialEq, Hash)]
 #[repr(usize)]
 pub enum EventTime {
     START = 0,
@@ -36,7 +28,7 @@ pub enum EventTime {
     END = 9,
 }
 
-#[derive(Debug, Clone, Copy, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Hash)]
 #[serde(rename_all = "UPPERCASE")]
 pub enum Stat {
     Power,
@@ -46,7 +38,7 @@ pub enum Stat {
     Pillz,
 }
 
-#[derive(Debug, Clone, Copy, Deserialize_repr, PartialEq)]
+#[derive(Debug, Clone, Copy, Serialize_repr, Deserialize_repr, PartialEq, Hash)]
 #[serde(untagged)]
 #[repr(u8)]
 pub enum Per {
@@ -65,9 +57,39 @@ pub enum Per {
     OppDamage = 13,
     OppLife = 14,
     OppPillz = 15,
+    /// Life under the starting life, none once a gain brings it back.
+    LifeLost = 16,
+    /// Pillz under the starting pillz, before this round's are spent.
+    PillzLost = 17,
 }
 
-#[derive(Debug, Clone, Copy, Deserialize_repr, PartialEq)]
+impl Per {
+    /// Whether `BasicModifier` computes a multiplier for this value. The
+    /// others multiply by 1.
+    pub fn is_handled(self) -> bool {
+        match self {
+            Per::Power
+            | Per::Damage
+            | Per::Life
+            | Per::Pillz
+            | Per::Support
+            | Per::Brawl
+            | Per::Growth
+            | Per::Degrowth
+            | Per::Equalizer
+            | Per::Symmetry
+            | Per::Asymmetry
+            | Per::OppPower
+            | Per::OppDamage
+            | Per::OppLife
+            | Per::OppPillz
+            | Per::LifeLost
+            | Per::PillzLost => true,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize_repr, Deserialize_repr, PartialEq, Hash)]
 #[repr(u8)]
 pub enum Cancel {
     Power = 1,
@@ -79,7 +101,7 @@ pub enum Cancel {
     Life = 7,
 }
 
-#[derive(Debug, Clone, Copy, Deserialize_repr, PartialEq)]
+#[derive(Debug, Clone, Copy, Serialize_repr, Deserialize_repr, PartialEq, Hash)]
 #[repr(u8)]
 pub enum Copy {
     Power = 1,
@@ -89,7 +111,7 @@ pub enum Copy {
     Infiltrate = 5,
 }
 
-#[derive(Debug, Clone, Copy, Deserialize_repr, PartialEq)]
+#[derive(Debug, Clone, Copy, Serialize_repr, Deserialize_repr, PartialEq, Hash)]
 #[repr(u8)]
 pub enum Exchange {
     Power = 1,
@@ -98,7 +120,7 @@ pub enum Exchange {
     ImposeDamage = 4,
 }
 
-#[derive(Debug, Clone, Copy, Deserialize_repr, PartialEq)]
+#[derive(Debug, Clone, Copy, Serialize_repr, Deserialize_repr, PartialEq, Hash)]
 #[repr(u8)]
 pub enum Protect {
     Power = 1,
@@ -108,70 +130,126 @@ pub enum Protect {
     Bonus = 5,
 }
 
-#[derive(Debug, Clone, Copy, Deserialize_repr, PartialEq)]
+#[derive(Debug, Clone, Copy, Serialize_repr, Deserialize_repr, PartialEq, Hash)]
 #[repr(u8)]
 pub enum Recover {
     Pillz = 1,
     Life = 2,
 }
 
-#[derive(Clone, Copy, Debug, Deserialize, PartialEq)]
+/// Abilities that change a rule of the round rather than a stat. Most only
+/// mark the card, and the rule they change checks `Card::keyword`.
+#[derive(Debug, Clone, Copy, Serialize_repr, Deserialize_repr, PartialEq, Eq, Hash)]
+#[repr(u8)]
+pub enum Keyword {
+    /// When the card loses, the winner's player loses 1 life.
+    CounterAttack = 1,
+    /// The card wins rounds its attack ties, before levels are compared.
+    TieBreak = 2,
+    /// The card's modifiers ignore the opponent's protections.
+    Bypass = 3,
+    /// The card's abilities apply without their conditions.
+    RemoveConditions = 4,
+    /// The opponent can't copy the card's power, damage, ability or bonus.
+    Illusion = 5,
+    /// When the card wins, the opponent loses 1 more life.
+    Hazard = 6,
+    /// The card's modifiers ignore their min and max.
+    Limitless = 7,
+    /// Every pill bet gives the card one more attack multiplier.
+    Overdose = 8,
+    /// The card's modifiers apply whether it wins or not.
+    Beyond = 9,
+    /// +1 power and damage per round the player already won.
+    Perfection = 10,
+    /// If both cards are in the same position, cancels the opponent's ability
+    /// and bonus.
+    SinisterSymmetry = 11,
+}
+
+impl Keyword {
+    /// Leader keywords are part of the leader ability, and stop working while
+    /// the leader is cancelled.
+    pub fn is_leader(self) -> bool {
+        !matches!(
+            self,
+            Keyword::Beyond | Keyword::Perfection | Keyword::SinisterSymmetry
+        )
+    }
+
+    /// When the keyword applies: leader keywords mark the card before any
+    /// other ability, the others once cancellations are settled.
+    pub fn event_time(self) -> EventTime {
+        match self {
+            Keyword::SinisterSymmetry => EventTime::PRE4,
+            Keyword::Perfection => EventTime::PRE2,
+            Keyword::Beyond => EventTime::PRE1,
+            _ => EventTime::START,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq, Hash)]
 pub struct BasicModifier {
     #[serde(rename = "eventTime")]
-    event_time: EventTime,
-    win: Option<bool>,
-    change: i32,
-    per: Option<Per>,
+    pub event_time: EventTime,
+    pub win: Option<bool>,
+    pub change: i32,
+    pub per: Option<Per>,
     #[serde(rename = "type")]
-    stat: Stat,
-    opp: bool,
-    min: i32,
-    max: i32,
-    always: bool,
+    pub stat: Stat,
+    pub opp: bool,
+    pub min: i32,
+    pub max: i32,
+    pub always: bool,
 }
 
 impl BasicModifier {
-    fn get_multiplier(&self, data: &BattleData) -> u8 {
-        if self.per == None {
+    /// `Per` values count the player's side and `Per::Opp*` the opponent's,
+    /// whichever side the modifier targets.
+    fn get_multiplier(&self, data: &BattleData) -> i32 {
+        let Some(per) = self.per else {
             return 1;
-        }
-
-        let (player, card) = if self.opp {
-            (data.opp.borrow(), data.opp_card.borrow())
-        } else {
-            (data.player.borrow(), data.card.borrow())
         };
 
+        let player = data.player.borrow();
+        let opp = data.opp.borrow();
         let player_card = data.card.borrow();
         let opp_card = data.opp_card.borrow();
-        match self.per.unwrap() {
-            Per::Power => card.power.value,
-            Per::Damage => card.damage.value,
-            Per::Life => player.life,
-            Per::Pillz => player.pillz,
-            Per::Support => data.hand.card_clan_count(player_card.index),
-            Per::Brawl => data.opp_hand.card_clan_count(opp_card.index),
-            Per::Growth => 1 + data.round,
-            Per::Degrowth => 4 - data.round,
-            Per::Equalizer => opp_card.level,
-            Per::Symmetry => (player_card.index == opp_card.index) as u8,
-            Per::Asymmetry => (player_card.index != opp_card.index) as u8,
-            _ => 1,
+        match per {
+            Per::Power => player_card.power.value as i32,
+            Per::Damage => player_card.damage.value as i32,
+            Per::Life => player.life as i32,
+            Per::Pillz => player.pillz as i32,
+            Per::Support => data.hand.card_clan_count(player_card.index) as i32,
+            Per::Brawl => data.opp_hand.card_clan_count(opp_card.index) as i32,
+            Per::Growth => 1 + data.round as i32,
+            Per::Degrowth => 4 - data.round as i32,
+            Per::Equalizer => opp_card.level as i32,
+            Per::Symmetry => (player_card.index == opp_card.index) as i32,
+            Per::Asymmetry => (player_card.index != opp_card.index) as i32,
+            Per::OppPower => opp_card.power.value as i32,
+            Per::OppDamage => opp_card.damage.value as i32,
+            Per::OppLife => opp.life as i32,
+            Per::OppPillz => opp.pillz as i32,
+            Per::LifeLost => player.life_start.saturating_sub(player.life) as i32,
+            Per::PillzLost => player.pillz_start.saturating_sub(player.pillz) as i32,
         }
     }
-    fn modify(&self, base: u8, data: &BattleData) -> u8 {
-        if (base as i32) < self.min || (base as i32) >= self.max {
-            return base as u8;
+    /// The modified value, in `i32` so it can't overflow. Callers clamp it to
+    /// the stat they store it in.
+    fn modify(&self, base: i32, data: &BattleData) -> i32 {
+        let (min, max) = if data.card.borrow().keyword(Keyword::Limitless) {
+            (0, i32::MAX)
+        } else {
+            (self.min, self.max)
+        };
+        if base < min || base >= max {
+            return base;
         }
 
-        let multiplier = self.get_multiplier(data) as i32;
-        let change = self.change * multiplier as i32;
-        let value = base as i32 + change;
-        let squash = value.max(self.min).min(self.max);
-
-        println!("{} => {} >=< {}", base, value, squash);
-
-        squash as u8
+        let change = self.change.saturating_mul(self.get_multiplier(data));
+        base.saturating_add(change).max(min).min(max)
     }
 }
 
@@ -180,28 +258,25 @@ impl BasicModifier {
         if self.always {
             return true;
         }
-        if self.win == Some(true) && !data.card.borrow().won {
+        let card = data.card.borrow();
+        if self.win == Some(true) && !card.won && !card.keyword(Keyword::Beyond) {
             return false;
         }
 
-        let card = data.card.borrow();
         if self.opp {
             let opp_card = data.opp_card.borrow();
-            println!("opp_card = {:?}", opp_card.life);
+            let bypass = card.keyword(Keyword::Bypass);
+            let protected = |attr: &CardAttr| attr.is_protected() && !bypass;
             match self.stat {
-                Stat::Power => !opp_card.power.attr.is_protected() && !card.power.attr.is_blocked(),
-                Stat::Damage => {
-                    !opp_card.damage.attr.is_protected() && !card.damage.attr.is_blocked()
-                }
-                Stat::Attack => {
-                    !opp_card.attack.attr.is_protected() && !card.attack.attr.is_blocked()
-                }
+                Stat::Power => !protected(&opp_card.power.attr) && !card.power.attr.is_blocked(),
+                Stat::Damage => !protected(&opp_card.damage.attr) && !card.damage.attr.is_blocked(),
+                Stat::Attack => !protected(&opp_card.attack.attr) && !card.attack.attr.is_blocked(),
                 Stat::Life => {
-                    !opp_card.life.is_protected()
+                    !protected(&opp_card.life)
                         && !card.life.is_blocked()
                         && data.player.borrow().life > 0
                 }
-                Stat::Pillz => !opp_card.pillz.is_protected() && !card.pillz.is_blocked(),
+                Stat::Pillz => !protected(&opp_card.pillz) && !card.pillz.is_blocked(),
             }
         } else {
             // println!("card = {:#?}", card);
@@ -215,54 +290,68 @@ impl BasicModifier {
         }
     }
     pub fn apply(&mut self, data: &BattleData) {
-        // println!(
-        //     "{}, {}",
-        //     data.card.borrow().ability.attr.blocked(),
-        //     data.card.borrow().bonus.attr.blocked(),
-        // );
         if self.can_apply(data) {
-            println!("Applying modifier: {:?}", self);
-
             let (card, player) = if self.opp {
                 (data.opp_card, data.opp)
             } else {
                 (data.card, data.player)
             };
 
-            match self.stat {
+            let (before, after) = match self.stat {
                 Stat::Power => {
-                    let val = self.modify(card.borrow().power.value, data);
+                    let before = card.borrow().power.value;
+                    let val = u16::clamp_from(self.modify(before as i32, data));
                     card.borrow_mut().power.value = val;
+                    (before, val)
                 }
                 Stat::Damage => {
-                    let val = self.modify(card.borrow().damage.value, data);
+                    let before = card.borrow().damage.value;
+                    let val = u16::clamp_from(self.modify(before as i32, data));
                     card.borrow_mut().damage.value = val;
+                    (before, val)
                 }
                 Stat::Attack => {
-                    let val = self.modify(card.borrow().attack.value, data);
+                    let before = card.borrow().attack.value;
+                    let val = u16::clamp_from(self.modify(before as i32, data));
                     card.borrow_mut().attack.value = val;
+                    (before, val)
                 }
                 Stat::Life => {
-                    let val = self.modify(player.borrow().life, data);
+                    let before = player.borrow().life;
+                    let val = Player::clamp_life(self.modify(before as i32, data));
                     player.borrow_mut().life = val;
+                    (before as u16, val as u16)
                 }
                 Stat::Pillz => {
-                    let val = self.modify(player.borrow().pillz, data);
+                    let before = player.borrow().pillz;
+                    let val = Player::clamp_pillz(self.modify(before as i32, data));
                     player.borrow_mut().pillz = val;
+                    (before as u16, val as u16)
                 }
-            }
+            };
+            data.log(BattleEvent::ModifierApplied {
+                player: data.player_type(),
+                stat: self.stat,
+                opp: self.opp,
+                before,
+                after,
+            });
         } else {
-            println!("Can't apply modifier: {:?}", self);
+            data.log(BattleEvent::ModifierBlocked {
+                player: data.player_type(),
+                stat: self.stat,
+                opp: self.opp,
+            });
         }
     }
 }
 
-#[derive(Clone, Copy, Debug, Deserialize, PartialEq)]
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq, Hash)]
 pub struct CancelModifier {
     #[serde(rename = "eventTime")]
-    event_time: EventTime,
-    win: Option<bool>,
-    cancel: Cancel,
+    pub event_time: EventTime,
+    pub win: Option<bool>,
+    pub cancel: Cancel,
     pub applied: Option<bool>,
 }
 
@@ -270,6 +359,10 @@ pub struct CancelModifier {
 impl CancelModifier {
     pub fn apply(&mut self, data: &BattleData) {
         self.applied = Some(true);
+        data.log(BattleEvent::Cancelled {
+            player: data.player_type(),
+            cancel: self.cancel,
+        });
         let mut opp_card = data.opp_card.borrow_mut();
         match self.cancel {
             Cancel::Power => opp_card.power.attr.cancel(),
@@ -285,6 +378,10 @@ impl CancelModifier {
     }
     pub fn undo(&mut self, data: &BattleData) {
         self.applied = Some(false);
+        data.log(BattleEvent::CancelUndone {
+            player: data.player_type(),
+            cancel: self.cancel,
+        });
         let mut opp_card = data.opp_card.borrow_mut();
         match self.cancel {
             Cancel::Power => opp_card.power.attr.remove_cancel(),
@@ -303,16 +400,23 @@ impl CancelModifier {
     // }
 }
 
-#[derive(Clone, Copy, Debug, Deserialize, PartialEq)]
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq, Hash)]
 pub struct CopyModifier {
     #[serde(rename = "eventTime")]
-    event_time: EventTime,
-    win: Option<bool>,
-    copy: Copy,
+    pub event_time: EventTime,
+    pub win: Option<bool>,
+    pub copy: Copy,
 }
 
 impl CopyModifier {
     pub fn apply(&mut self, data: &BattleData) -> Option<Ability> {
+        if self.copy != Copy::Infiltrate && data.opp_card.borrow().keyword(Keyword::Illusion) {
+            return None;
+        }
+        data.log(BattleEvent::Copied {
+            player: data.player_type(),
+            copy: self.copy,
+        });
         let mut card = data.card.borrow_mut();
         let opp_card = data.opp_card.borrow();
         match self.copy {
@@ -323,10 +427,12 @@ impl CopyModifier {
                 card.bonus_id = opp_card.ability_id;
 
                 let mut bonus = card.get_bonus();
+                // A copied leader ability only applies for the round
                 match &bonus.ability_type {
-                    AbilityType::Ability => bonus.ability_type = AbilityType::Bonus,
+                    AbilityType::Ability | AbilityType::Global => {
+                        bonus.ability_type = AbilityType::Bonus
+                    }
                     AbilityType::GlobalAbility => bonus.ability_type = AbilityType::GlobalBonus,
-                    AbilityType::Global => return None,
                     _ => (),
                 }
 
@@ -338,9 +444,10 @@ impl CopyModifier {
 
                 let mut ability = card.get_ability();
                 match &ability.ability_type {
-                    AbilityType::Bonus => ability.ability_type = AbilityType::Ability,
+                    AbilityType::Bonus | AbilityType::Global => {
+                        ability.ability_type = AbilityType::Ability
+                    }
                     AbilityType::GlobalBonus => ability.ability_type = AbilityType::GlobalAbility,
-                    AbilityType::Global => return None,
                     _ => (),
                 }
 
@@ -367,16 +474,20 @@ impl CopyModifier {
     }
 }
 
-#[derive(Clone, Copy, Debug, Deserialize, PartialEq)]
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq, Hash)]
 pub struct ExchangeModifier {
     #[serde(rename = "eventTime")]
-    event_time: EventTime,
-    win: Option<bool>,
-    ex: Exchange,
+    pub event_time: EventTime,
+    pub win: Option<bool>,
+    pub ex: Exchange,
 }
 
 impl ExchangeModifier {
     pub fn apply(&mut self, data: &BattleData) {
+        data.log(BattleEvent::Exchanged {
+            player: data.player_type(),
+            exchange: self.ex,
+        });
         let mut card = data.card.borrow_mut();
         let mut opp_card = data.opp_card.borrow_mut();
         match self.ex {
@@ -406,17 +517,22 @@ impl ExchangeModifier {
     }
 }
 
-#[derive(Clone, Copy, Debug, Deserialize, PartialEq)]
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq, Hash)]
 pub struct ProtectionModifier {
     #[serde(rename = "eventTime")]
-    event_time: EventTime,
-    win: Option<bool>,
-    prot: Protect,
-    both: bool,
+    pub event_time: EventTime,
+    pub win: Option<bool>,
+    pub prot: Protect,
+    pub both: bool,
 }
 
 impl ProtectionModifier {
     pub fn apply(&mut self, data: &BattleData) {
+        data.log(BattleEvent::Protected {
+            player: data.player_type(),
+            protect: self.prot,
+            both: self.both,
+        });
         let mut card = data.card.borrow_mut();
         if self.both {
             let mut opp_card = data.opp_card.borrow_mut();
@@ -456,40 +572,200 @@ impl ProtectionModifier {
                 Protect::Ability => card.ability.protect(),
                 Protect::Bonus => card.bonus.protect(),
             };
-            println!("{:?}", card.power.attr);
         }
     }
 }
 
-#[derive(Clone, Copy, Debug, Deserialize, PartialEq)]
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq, Hash)]
 pub struct RecoverModifier {
     #[serde(rename = "eventTime")]
-    event_time: EventTime,
-    win: Option<bool>,
+    pub event_time: EventTime,
+    pub win: Option<bool>,
     // rec: Recover,
-    n: u8,
+    pub n: u8,
     #[serde(rename = "outOf")]
-    out_of: u8,
+    pub out_of: u8,
 }
 
 impl RecoverModifier {
     fn apply(&mut self, data: &BattleData) {
         if !data.card.borrow().pillz.is_blocked() {
-            #[inline(always)]
-            fn ceil_divide(top: u8, bottom: u8) -> u8 {
-                (top + bottom - 1) / bottom
+            let used = data.player_pillz_used as i32 * self.n as i32;
+            let gain = u8::clamp_from((used + self.out_of as i32 - 1) / self.out_of as i32);
+            let mut player = data.player.borrow_mut();
+            let pillz = player.pillz as i32 + gain as i32;
+            player.set_pillz(pillz);
+            data.log(BattleEvent::PillzRecovered {
+                player: data.player_type(),
+                amount: gain,
+            });
+        }
+    }
+}
+
+/// Cancels a side's leader ability for the round.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq, Hash)]
+pub struct CancelLeaderModifier {
+    #[serde(rename = "eventTime")]
+    pub event_time: EventTime,
+    pub win: Option<bool>,
+    /// Cancels the opponent's leader, or the player's own.
+    #[serde(rename = "leader")]
+    pub opp: bool,
+}
+
+impl CancelLeaderModifier {
+    fn apply(&mut self, data: &BattleData) {
+        let card = if self.opp { data.opp_card } else { data.card };
+        card.borrow_mut().leader.cancel();
+        data.log(BattleEvent::LeaderCancelled {
+            player: data.player_type(),
+            opp: self.opp,
+        });
+    }
+}
+
+/// The card ignores the opponent for the round: the opponent's ability,
+/// bonus and leader can't change its stats, the player's life and pillz, or
+/// stop its own ability, bonus and leader.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq, Hash)]
+pub struct TuneOutModifier {
+    #[serde(rename = "eventTime")]
+    pub event_time: EventTime,
+    pub win: Option<bool>,
+    /// Both cards ignore each other.
+    #[serde(rename = "tuneOut")]
+    pub both: bool,
+}
+
+impl TuneOutModifier {
+    fn apply(&mut self, data: &BattleData) {
+        data.log(BattleEvent::TunedOut {
+            player: data.player_type(),
+            both: self.both,
+        });
+        let cards: &[_] = if self.both {
+            &[data.card, data.opp_card]
+        } else {
+            &[data.card]
+        };
+        for card in cards {
+            let mut card = card.borrow_mut();
+            card.power.attr.protect();
+            card.damage.attr.protect();
+            card.attack.attr.protect();
+            card.ability.protect();
+            card.bonus.protect();
+            card.life.protect();
+            card.pillz.protect();
+            card.leader.protect();
+        }
+    }
+}
+
+/// Leaves a status effect on a player, see `battle::Statuses`.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq, Hash)]
+pub struct StatusModifier {
+    #[serde(rename = "eventTime")]
+    pub event_time: EventTime,
+    pub win: Option<bool>,
+    pub status: StatusEffect,
+    pub opp: bool,
+}
+
+impl StatusModifier {
+    fn apply(&mut self, data: &BattleData) {
+        let card = data.card.borrow();
+        let blocked = if self.opp {
+            let protected =
+                data.opp_card.borrow().life.is_protected() && !card.keyword(Keyword::Bypass);
+            protected || card.life.is_blocked()
+        } else {
+            card.life.is_blocked()
+        };
+        let lost = self.win == Some(true) && !card.won && !card.keyword(Keyword::Beyond);
+        if blocked || lost {
+            return;
+        }
+
+        let player = if self.opp { data.opp } else { data.player };
+        player.borrow_mut().statuses.add(self.status);
+        data.log(BattleEvent::StatusAdded {
+            player: data.player_type(),
+            kind: self.status.kind,
+            opp: self.opp,
+        });
+    }
+}
+
+/// Gives the card a keyword for the round, see `Keyword`.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq, Hash)]
+pub struct KeywordModifier {
+    #[serde(rename = "eventTime")]
+    pub event_time: EventTime,
+    pub win: Option<bool>,
+    pub keyword: Keyword,
+}
+
+impl KeywordModifier {
+    fn apply(&mut self, data: &BattleData) {
+        data.card.borrow_mut().add_keyword(self.keyword);
+        data.log(BattleEvent::Keyword {
+            player: data.player_type(),
+            keyword: self.keyword,
+        });
+
+        match self.keyword {
+            Keyword::Perfection => {
+                let won = data
+                    .hand
+                    .cards
+                    .iter()
+                    .filter(|card| {
+                        let card = card.borrow();
+                        card.played && card.won
+                    })
+                    .count() as u8;
+                let mut card = data.card.borrow_mut();
+                let card = &mut **card;
+                for (stat, value) in [
+                    (Stat::Power, &mut card.power),
+                    (Stat::Damage, &mut card.damage),
+                ] {
+                    if value.attr.is_blocked() {
+                        continue;
+                    }
+                    let before = value.value;
+                    value.value = before.saturating_add(won as u16);
+                    data.log(BattleEvent::ModifierApplied {
+                        player: data.player_type(),
+                        stat,
+                        opp: false,
+                        before,
+                        after: value.value,
+                    });
+                }
+            }
+            Keyword::SinisterSymmetry => {
+                if data.card.borrow().index != data.opp_card.borrow().index {
+                    return;
+                }
+                let mut opp_card = data.opp_card.borrow_mut();
+                opp_card.ability.cancel();
+                opp_card.bonus.cancel();
+                for cancel in [Cancel::Ability, Cancel::Bonus] {
+                    data.log(BattleEvent::Cancelled {
+                        player: data.player_type(),
+                        cancel,
+                    });
+                }
             }
-            let gain = ceil_divide(data.player_pillz_used * self.n, self.out_of);
-            println!(
-                "Player recovered {} pillz / {}",
-                gain, data.player_pillz_used
-            );
-            data.player.borrow_mut().pillz += gain;
+            _ => (),
         }
     }
 }
 
-#[derive(Debug, Clone, Copy, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Hash)]
 #[serde(untagged)]
 pub enum Modifier {
     Basic(BasicModifier),
@@ -498,6 +774,10 @@ pub enum Modifier {
     Exchange(ExchangeModifier),
     Protection(ProtectionModifier),
     Recover(RecoverModifier),
+    Status(StatusModifier),
+    CancelLeader(CancelLeaderModifier),
+    TuneOut(TuneOutModifier),
+    Keyword(KeywordModifier),
 }
 
 impl Modifier {
@@ -510,6 +790,10 @@ impl Modifier {
             Modifier::Exchange(inner) => inner.event_time,
             Modifier::Protection(inner) => inner.event_time,
             Modifier::Recover(inner) => inner.event_time,
+            Modifier::Status(inner) => inner.event_time,
+            Modifier::CancelLeader(inner) => inner.event_time,
+            Modifier::TuneOut(inner) => inner.event_time,
+            Modifier::Keyword(inner) => inner.event_time,
         }
     }
 
@@ -522,7 +806,178 @@ impl Modifier {
             Modifier::Exchange(inner) => inner.apply(data),
             Modifier::Protection(inner) => inner.apply(data),
             Modifier::Recover(inner) => inner.apply(data),
+            Modifier::Status(inner) => inner.apply(data),
+            Modifier::CancelLeader(inner) => inner.apply(data),
+            Modifier::TuneOut(inner) => inner.apply(data),
+            Modifier::Keyword(inner) => inner.apply(data),
         };
         None
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+
+    use super::{BasicModifier, EventTime, Per, Stat};
+    use crate::{
+        battle::{BattleData, BattleLog, Events},
+        card::Hand,
+        game::{Player, PlayerType, MAX_PILLZ},
+    };
+
+    /// Runs `f` in round 2 with the player's first card against the
+    /// opponent's second.
+    fn with_data(f: impl FnOnce(&BattleData)) {
+        let mut h1 = Hand::from_names("Bazalt", "Elvis", "Danae", "Vivian");
+        let mut h2 = Hand::from_names("Cell", "John O Clock", "Hollow Spyke", "Dr Falkenstein");
+        h1.cards[0].power.value = 3;
+        h1.cards[0].damage.value = 4;
+        h2.cards[1].power.value = 6;
+        h2.cards[1].damage.value = 7;
+        h2.cards[1].level = 4;
+        h1.clan_count[0] = 2;
+        h2.clan_count[1] = 3;
+
+        let mut p1 = Player::new(PlayerType::Player);
+        let mut p2 = Player::new(PlayerType::Opponent);
+        (p1.life, p1.pillz) = (10, 5);
+        (p2.life, p2.pillz) = (9, 2);
+        let mut events = Events::default();
+
+        let h1 = h1.to_handcell();
+        let h2 = h2.to_handcell();
+        let p1 = RefCell::new(&mut p1);
+        let p2 = RefCell::new(&mut p2);
+        let events = RefCell::new(&mut events);
+        let log = RefCell::new(BattleLog::new(false));
+        f(&BattleData {
+            round: 1,
+            day: true,
+            first: true,
+            player: &p1,
+            hand: &h1,
+            card: &h1.cards[0],
+            player_pillz_used: 0,
+            opp: &p2,
+            opp_hand: &h2,
+            opp_card: &h2.cards[1],
+            opp_pillz_used: 0,
+            events: &events,
+            log: &log,
+        });
+    }
+
+    fn modifier(per: Per, opp: bool) -> BasicModifier {
+        BasicModifier {
+            event_time: EventTime::POST1,
+            win: None,
+            change: 1,
+            per: Some(per),
+            stat: Stat::Attack,
+            opp,
+            min: -1000,
+            max: 1000,
+            always: false,
+        }
+    }
+
+    #[test]
+    fn multipliers() {
+        with_data(|data| {
+            for (per, expected) in [
+                (Per::Power, 3),
+                (Per::Damage, 4),
+                (Per::Life, 10),
+                (Per::Pillz, 5),
+                (Per::Support, 2),
+                (Per::Brawl, 3),
+                (Per::Growth, 2),
+                (Per::Degrowth, 3),
+                (Per::Equalizer, 4),
+                (Per::Symmetry, 0),
+                (Per::Asymmetry, 1),
+                (Per::OppPower, 6),
+                (Per::OppDamage, 7),
+                (Per::OppLife, 9),
+                (Per::OppPillz, 2),
+                (Per::LifeLost, 2),
+                (Per::PillzLost, 7),
+            ] {
+                // Whose stat is counted doesn't depend on who is targeted
+                for opp in [false, true] {
+                    let multiplier = modifier(per, opp).get_multiplier(data);
+                    assert_eq!(multiplier, expected, "{:?}, opp: {}", per, opp);
+                }
+            }
+        });
+    }
+
+    #[test]
+    fn lost_counts_from_the_start() {
+        with_data(|data| {
+            // Started with 8 life and 3 pillz, and lost 2 life since
+            data.player.borrow_mut().start_with(8, 3);
+            (
+                data.player.borrow_mut().life,
+                data.player.borrow_mut().pillz,
+            ) = (6, 3);
+            assert_eq!(modifier(Per::LifeLost, false).get_multiplier(data), 2);
+            assert_eq!(modifier(Per::PillzLost, false).get_multiplier(data), 0);
+
+            // A gain past the start doesn't count as a negative loss
+            data.player.borrow_mut().life = 10;
+            data.player.borrow_mut().pillz = 1;
+            assert_eq!(modifier(Per::LifeLost, true).get_multiplier(data), 0);
+            assert_eq!(modifier(Per::PillzLost, true).get_multiplier(data), 2);
+        });
+    }
+
+    #[test]
+    fn opp_per_applies() {
+        with_data(|data| {
+            data.card.borrow_mut().attack.value = 10;
+            modifier(Per::OppDamage, false).apply(data);
+            assert_eq!(data.card.borrow().attack.value, 17);
+
+            data.opp_card.borrow_mut().attack.value = 20;
+            let mut penalty = modifier(Per::Damage, true);
+            penalty.change = -1;
+            penalty.apply(data);
+            assert_eq!(data.opp_card.borrow().attack.value, 16);
+        });
+    }
+
+    #[test]
+    fn clamps_extremes() {
+        with_data(|data| {
+            // 250 + 8 * 7 doesn't wrap
+            data.card.borrow_mut().attack.value = 250;
+            let mut bonus = modifier(Per::OppDamage, false);
+            bonus.change = 8;
+            bonus.apply(data);
+            assert_eq!(data.card.borrow().attack.value, 306);
+
+            // A penalty without a min stops at 0
+            data.opp_card.borrow_mut().attack.value = 5;
+            let mut penalty = modifier(Per::Damage, true);
+            penalty.change = -12;
+            penalty.apply(data);
+            assert_eq!(data.opp_card.borrow().attack.value, 0);
+
+            // Life stops at 255
+            data.player.borrow_mut().life = 250;
+            let mut heal = modifier(Per::OppPower, false);
+            heal.stat = Stat::Life;
+            heal.apply(data);
+            assert_eq!(data.player.borrow().life, 255);
+
+            // Pillz stop where the solvers' range tables do
+            data.player.borrow_mut().pillz = 30;
+            let mut gain = modifier(Per::OppPower, false);
+            gain.stat = Stat::Pillz;
+            gain.apply(data);
+            assert_eq!(data.player.borrow().pillz, MAX_PILLZ);
+        });
+    }
+}
@@ -0,0 +1,45 @@
+use std::fmt::Debug;
+
+use crate::game::Game;
+
+/// Where a game sends its output. Every `Game` carries its own sink, so a
+/// search can run silently while another game keeps printing.
+pub trait Output: Debug + Sync {
+    /// Prints the board between battles.
+    fn status(&self, game: &Game);
+    /// Prints the two selected cards after a battle.
+    fn battle(&self, game: &Game, attack1: u16, attack2: u16);
+    /// Whether battles should record a `BattleLog`.
+    fn logging(&self) -> bool;
+}
+
+/// Prints everything to stdout and records battle logs.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Terminal;
+
+impl Output for Terminal {
+    fn status(&self, game: &Game) {
+        game.render_status();
+    }
+    fn battle(&self, game: &Game, attack1: u16, attack2: u16) {
+        game.render_battle(attack1, attack2);
+    }
+    fn logging(&self) -> bool {
+        true
+    }
+}
+
+/// Prints nothing and records nothing. Used by the solvers.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Silent;
+
+impl Output for Silent {
+    #[inline]
+    fn status(&self, _: &Game) {}
+    #[inline]
+    fn battle(&self, _: &Game, _: u16, _: u16) {}
+    #[inline]
+    fn logging(&self) -> bool {
+        false
+    }
+}
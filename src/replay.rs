@@ -0,0 +1,239 @@
+use std::{
+    array,
+    fmt::{Display, Formatter},
+    fs::{self, File},
+    io::{self, BufReader},
+    path::{Path, PathBuf},
+};
+
+use chrono::Local;
+use colored::Colorize;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    card::{CardError, Hand},
+    game::{Game, GameStatus, Selection},
+    solver::{self, Options},
+};
+
+/// Bumped whenever the replay layout changes.
+pub const REPLAY_VERSION: u32 = 1;
+/// Where the CLI and the server write their replays.
+pub const REPLAY_DIR: &str = "./replays";
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ReplayCard {
+    pub id: u32,
+    pub level: u8,
+}
+
+/// A full match: the starting position and every selection in the order it
+/// was played.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Replay {
+    pub version: u32,
+    pub hands: [[ReplayCard; 4]; 2],
+    pub flip: u8,
+    pub day: bool,
+    pub life: [u8; 2],
+    pub pillz: [u8; 2],
+    pub selections: Vec<Selection>,
+}
+
+#[derive(Debug)]
+pub enum ReplayError {
+    Io(io::Error),
+    Json(serde_json::Error),
+    /// The replay was written by a different replay version.
+    Version(u32),
+    /// A hand has an unknown card id or an invalid level.
+    Card(CardError),
+}
+
+impl Display for ReplayError {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        match self {
+            ReplayError::Io(e) => write!(f, "Can't read replay: {}", e),
+            ReplayError::Json(e) => write!(f, "Invalid replay: {}", e),
+            ReplayError::Version(v) => write!(
+                f,
+                "Unsupported replay version {} (expected {})",
+                v, REPLAY_VERSION
+            ),
+            ReplayError::Card(e) => write!(f, "Invalid replay: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for ReplayError {}
+
+impl Replay {
+    /// Starts recording a game that hasn't been played yet.
+    pub fn new(game: &Game) -> Self {
+        let cards = |hand: &Hand| {
+            hand.cards.map(|card| ReplayCard {
+                id: card.id,
+                level: card.level,
+            })
+        };
+        Replay {
+            version: REPLAY_VERSION,
+            hands: [cards(&game.h1), cards(&game.h2)],
+            flip: game.flip,
+            day: game.day,
+            life: [game.p1.life, game.p2.life],
+            pillz: [game.p1.pillz, game.p2.pillz],
+            selections: Vec::new(),
+        }
+    }
+
+    /// A new file name in `REPLAY_DIR`, e.g. `replays/20240131-184502.json`.
+    pub fn new_path(suffix: &str) -> PathBuf {
+        let name = Local::now().format("%Y%m%d-%H%M%S").to_string();
+        let name = if suffix.is_empty() {
+            name
+        } else {
+            format!("{}-{}", name, suffix)
+        };
+        Path::new(REPLAY_DIR).join(name).with_extension("json")
+    }
+
+    #[inline]
+    pub fn push(&mut self, selection: Selection) {
+        self.selections.push(selection);
+    }
+
+    /// Drops the pending selection, if any, when a player takes it back.
+    /// Call before `Game::clear_selection`.
+    pub fn cancel(&mut self, game: &Game) {
+        if game.has_someone_selected() {
+            self.selections.pop();
+        }
+    }
+
+    /// The game as it was before the first selection. Fails on a card the
+    /// assets don't have at the recorded level.
+    pub fn game(&self) -> Result<Game, ReplayError> {
+        let hand = |cards: &[ReplayCard; 4]| {
+            Hand::try_from_ids_levels(array::from_fn(|i| (cards[i].id, cards[i].level)))
+                .map_err(ReplayError::Card)
+        };
+        let mut game = Game::new(hand(&self.hands[0])?, hand(&self.hands[1])?);
+        game.flip = self.flip;
+        game.day = self.day;
+        game.p1.start_with(self.life[0], self.pillz[0]);
+        game.p2.start_with(self.life[1], self.pillz[1]);
+        Ok(game)
+    }
+
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let file = File::create(path)?;
+        serde_json::to_writer_pretty(file, self).map_err(io::Error::from)
+    }
+
+    pub fn load(path: &Path) -> Result<Self, ReplayError> {
+        let file = File::open(path).map_err(ReplayError::Io)?;
+        let replay: Replay =
+            serde_json::from_reader(BufReader::new(file)).map_err(ReplayError::Json)?;
+        if replay.version != REPLAY_VERSION {
+            return Err(ReplayError::Version(replay.version));
+        }
+        Ok(replay)
+    }
+
+    /// Steps through the match, printing every battle and the solver's pick
+    /// before each move. Returns how many moves differed from the solver.
+    pub fn run(&self, options: &Options) -> Result<usize, ReplayError> {
+        let mut game = self.game()?;
+        game.print_status();
+
+        let mut differed = 0;
+        for &selection in self.selections.iter() {
+            if game.status() != GameStatus::Playing {
+                break;
+            }
+            println!("{} turn", game.get_turn_name());
+
+            let best = solver::recommend(&game, options);
+            if selection == best {
+                println!("{} {}", " Played ".black().on_green(), selection);
+            } else {
+                differed += 1;
+                println!(
+                    "{} {}  {} {}",
+                    " Played ".black().on_red(),
+                    selection,
+                    " Solver ".black().on_bright_green(),
+                    best
+                );
+            }
+
+            let Selection { index, pillz, fury } = selection;
+            if !game.can_select(index, pillz, fury) {
+                println!("{} {}", " Invalid selection ".black().on_red(), selection);
+                break;
+            }
+            if let Some(log) = game.select(index, pillz, fury) {
+                print!("{}", log);
+            }
+        }
+        game.print_status();
+
+        println!(
+            "{} of {} moves differed from the solver",
+            differed,
+            self.selections.len()
+        );
+        Ok(differed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, fs};
+
+    use super::Replay;
+    use urban_recreation_rust::{
+        card::Hand,
+        game::{Game, Selection},
+        output::Silent,
+    };
+
+    #[test]
+    fn replay_round_trip() {
+        let h1 = Hand::from_names("Figaro", "Elvis:4", "Danae", "Vivian");
+        let h2 = Hand::from_names("Cell", "John O Clock:5", "Hollow Spyke", "Dr Falkenstein");
+        let mut game = Game::new(h1, h2);
+        game.output = &Silent;
+        game.flip = 1;
+        game.day = false;
+        game.p1.start_with(10, 12);
+
+        let mut replay = Replay::new(&game);
+        for (index, pillz) in [(0, 3), (0, 2), (1, 1), (1, 4), (2, 0)] {
+            game.select(index, pillz, false);
+            replay.push(Selection::new(index, pillz, false));
+        }
+        // Take back the last selection and play another card instead
+        replay.cancel(&game);
+        game.clear_selection();
+        game.select(3, 0, false);
+        replay.push(Selection::new(3, 0, false));
+
+        let path = env::temp_dir().join("urban_replay_round_trip.json");
+        replay.save(&path).unwrap();
+        let loaded = Replay::load(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(loaded, replay);
+
+        let mut replayed = loaded.game().unwrap();
+        replayed.output = &Silent;
+        for s in loaded.selections.iter() {
+            replayed.select(s.index, s.pillz, s.fury);
+        }
+        assert_eq!(replayed.snapshot().to_json(), game.snapshot().to_json());
+    }
+}
@@ -1,173 +1,793 @@
-use std::sync::Mutex;
+use std::{
+    collections::HashMap,
+    path::PathBuf,
+    sync::{Arc, Mutex, TryLockError},
+    time::{Duration, Instant},
+};
 
 use actix_cors::Cors;
-use actix_web::{post, web::Json, App, HttpResponse, HttpServer, Responder};
-use lazy_static::lazy_static;
-use serde::Deserialize;
+use actix_web::{
+    delete,
+    error::BlockingError,
+    get, post, rt,
+    web::{self, Data, Json, Path},
+    App, HttpResponse, HttpServer, Responder,
+};
+use serde::{Deserialize, Serialize};
 
 use crate::{
-    card::Hand,
-    game::{Game, GameStatus, PlayerType, Selection},
-    solver::{SelectionResult, Solver},
+    battle::{BattleEvent, BattleLog},
+    card::{Card, CardError, Hand},
+    game::{Game, GameStatus, PlayerType, Selection, MAX_PILLZ},
+    replay::Replay,
+    snapshot::Snapshot,
+    solver::{self, Engine, Evaluation, MiddleResult, Options, SelectionResult},
 };
 
-lazy_static! {
-    static ref GAME: Mutex<Option<Game>> = Mutex::new(None);
+/// Sessions left idle for longer than this are dropped.
+const SESSION_TTL: Duration = Duration::from_secs(30 * 60);
+/// How often idle sessions are looked for.
+const EXPIRE_INTERVAL: Duration = Duration::from_secs(60);
+/// Session played through the legacy `/` route.
+const DEFAULT_SESSION: &str = "default";
+
+struct Session {
+    game: Game,
+    options: Options,
+    last_used: Instant,
+    /// Recorded for games set up at their first round, not resumed ones.
+    replay: Option<(Replay, PathBuf)>,
+}
+
+/// Why a session couldn't be used.
+#[derive(Debug, PartialEq)]
+pub enum SessionError {
+    /// No session has this id, or it expired.
+    NotFound,
+    /// A selection panicked while holding the session, so its game may be
+    /// half updated. The session is dropped.
+    Poisoned,
+}
+
+impl SessionError {
+    fn response(&self) -> HttpResponse {
+        match self {
+            SessionError::NotFound => HttpResponse::NotFound().finish(),
+            SessionError::Poisoned => HttpResponse::InternalServerError().json(GameError {
+                error: "The game failed on the last selection and was dropped".to_string(),
+                suggestions: Vec::new(),
+            }),
+        }
+    }
+}
+
+/// Games being played through the server, keyed by session id.
+///
+/// Each session has its own lock, so one session's moves never wait on
+/// another's. Solvers run on a copy of the game after the lock is released.
+pub struct Sessions {
+    ttl: Duration,
+    /// Solver options of games that don't pick their own.
+    options: Options,
+    games: Mutex<HashMap<String, Arc<Mutex<Session>>>>,
 }
 
-pub async fn serve() -> Result<(), std::io::Error> {
-    HttpServer::new(|| {
+impl Sessions {
+    pub fn new(ttl: Duration, options: Options) -> Self {
+        Sessions {
+            ttl,
+            options,
+            games: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Stores a new game and returns its id.
+    pub fn create(&self, game: Game, options: Options) -> String {
+        let id = format!("{:016x}", rand::random::<u64>());
+        self.insert(id.clone(), game, options);
+        id
+    }
+
+    /// Stores a game under `id`, replacing any game already there.
+    pub fn insert(&self, id: String, game: Game, options: Options) {
+        let replay = (game.round == 0 && !game.has_someone_selected())
+            .then(|| (Replay::new(&game), Replay::new_path(&id)));
+        let session = Session {
+            game,
+            options,
+            last_used: Instant::now(),
+            replay,
+        };
+        self.games
+            .lock()
+            .unwrap()
+            .insert(id, Arc::new(Mutex::new(session)));
+    }
+
+    /// Options for a new game: its own engine and evaluation, else the
+    /// server's.
+    fn options(&self, engine: Option<Engine>, eval: Option<Evaluation>) -> Options {
+        Options {
+            engine: engine.unwrap_or(self.options.engine),
+            eval: eval.unwrap_or(self.options.eval),
+        }
+    }
+
+    /// Runs `f` on the game stored under `id`, marking the session as used.
+    pub fn with_game<T>(
+        &self,
+        id: &str,
+        f: impl FnOnce(&mut Game) -> T,
+    ) -> Result<T, SessionError> {
+        self.with_session(id, |session| f(&mut session.game))
+    }
+
+    fn with_session<T>(
+        &self,
+        id: &str,
+        f: impl FnOnce(&mut Session) -> T,
+    ) -> Result<T, SessionError> {
+        let session = self.games.lock().unwrap().get(id).cloned();
+        let session = session.ok_or(SessionError::NotFound)?;
+        let Ok(mut session) = session.lock() else {
+            self.remove(id);
+            return Err(SessionError::Poisoned);
+        };
+        session.last_used = Instant::now();
+        Ok(f(&mut session))
+    }
+
+    pub fn remove(&self, id: &str) -> bool {
+        self.games.lock().unwrap().remove(id).is_some()
+    }
+
+    #[cfg(test)]
+    pub fn len(&self) -> usize {
+        self.games.lock().unwrap().len()
+    }
+
+    /// Drops every session idle for longer than the ttl, or poisoned by a
+    /// panic. Sessions that are busy right now are kept.
+    pub fn expire(&self, now: Instant) {
+        self.games
+            .lock()
+            .unwrap()
+            .retain(|_, session| match session.try_lock() {
+                Ok(session) => now.duration_since(session.last_used) <= self.ttl,
+                Err(TryLockError::Poisoned(_)) => false,
+                Err(TryLockError::WouldBlock) => true,
+            });
+    }
+}
+
+pub async fn serve(host: &str, port: u16, options: Options) -> Result<(), std::io::Error> {
+    let sessions = Data::new(Sessions::new(SESSION_TTL, options));
+
+    // Idle sessions go away even when no request comes in
+    let expiring = sessions.clone();
+    rt::spawn(async move {
+        let mut interval = rt::time::interval(EXPIRE_INTERVAL);
+        loop {
+            interval.tick().await;
+            expiring.expire(Instant::now());
+        }
+    });
+
+    HttpServer::new(move || {
         App::new()
             .wrap(Cors::permissive())
-            // .app_data(Data::new(Mutex::<Option<Game>>::new(None)))
+            .app_data(sessions.clone())
             .service(input)
+            .service(create_game)
+            .service(resume_game)
+            .service(get_game)
+            .service(get_snapshot)
+            .service(play_game)
+            .service(delete_game)
     })
-    .bind(("127.0.0.1", 8080))?
+    .bind((host, port))?
     .run()
     .await
 }
 
+/// Legacy route playing a single shared session.
 #[post("/")]
-async fn input(data: Json<Input>) -> impl Responder {
+async fn input(sessions: Data<Sessions>, data: Json<Input>) -> impl Responder {
     println!("data -> {:?}", data);
-    // let mut game = state.lock().unwrap();
-    let mut game = GAME.lock().unwrap();
     match data.0 {
-        Input::Game {
-            cards,
-            flip,
-            life,
-            pillz,
-        } => {
-            let h1 = Hand::from_names(
-                cards[0].as_str(),
-                cards[1].as_str(),
-                cards[2].as_str(),
-                cards[3].as_str(),
-            );
-            let h2 = Hand::from_names(
-                cards[4].as_str(),
-                cards[5].as_str(),
-                cards[6].as_str(),
-                cards[7].as_str(),
-            );
-
-            let mut g = Game::new(h1, h2);
-            g.flip = flip;
-            g.p1.life = life;
-            g.p2.life = life;
-            g.p1.pillz = pillz;
-            g.p2.pillz = pillz;
-            *game = Some(g.clone());
-
-            g.print_status();
-
-            if flip == 0 {
-                // actix_web::web::block(move || {
-                //     println!("Blocking setup");
-                Solver::middle(&g);
-                //     println!("Unblocking setup");
-                // })
-                // .await
-                // .expect("Error in setup block");
+        Input::Game(new_game) => {
+            let options = sessions.options(new_game.solver, new_game.eval);
+            let game = match new_game.into_game() {
+                Ok(game) => game,
+                Err(error) => return HttpResponse::BadRequest().json(error),
+            };
+            sessions.insert(DEFAULT_SESSION.to_string(), game, options);
+            match setup(DEFAULT_SESSION, game, options).await {
+                Ok(response) => HttpResponse::Created().json(response),
+                Err(e) => solver_failed(e),
             }
+        }
+        Input::Move(play_move) => play(&sessions, DEFAULT_SESSION, play_move).await,
+    }
+}
 
-            println!("{} turn", g.get_turn_name());
-        }
-        // Input::Cancel { cancel: _ } => {
-        //     if let Some(game) = game.as_mut() {
-        //         game.clear_selection();
-        //         game.print_status();
-        //     }
-        // }
-        Input::Selection(Selection { index, pillz, fury }) => {
-            if let Some(game) = game.as_mut() {
-                select(game, index, pillz, fury, false).await;
-            } else {
-                println!("{:?}", game);
-            }
+#[post("/games")]
+async fn create_game(sessions: Data<Sessions>, data: Json<NewGame>) -> impl Responder {
+    println!("data -> {:?}", data);
+
+    let options = sessions.options(data.solver, data.eval);
+    let game = match data.0.into_game() {
+        Ok(game) => game,
+        Err(error) => return HttpResponse::BadRequest().json(error),
+    };
+    let id = sessions.create(game, options);
+
+    match setup(&id, game, options).await {
+        Ok(response) => HttpResponse::Created().json(response),
+        Err(e) => solver_failed(e),
+    }
+}
+
+/// Starts a session from a snapshot saved by `GET /games/{id}/snapshot` or
+/// by the console.
+#[post("/snapshots")]
+async fn resume_game(sessions: Data<Sessions>, data: Json<Resume>) -> impl Responder {
+    let options = sessions.options(data.solver, data.eval);
+    let game = match Game::from_snapshot(data.0.snapshot) {
+        Ok(game) => game,
+        Err(e) => {
+            return HttpResponse::BadRequest().json(GameError {
+                error: e.to_string(),
+                suggestions: Vec::new(),
+            })
         }
-        Input::CancelSelection {
+    };
+    let id = sessions.create(game, options);
+    game.print_status();
+    HttpResponse::Created().json(Response::new(&id, &game))
+}
+
+#[get("/games/{id}/snapshot")]
+async fn get_snapshot(sessions: Data<Sessions>, id: Path<String>) -> impl Responder {
+    match sessions.with_game(&id, |game| game.snapshot()) {
+        Ok(snapshot) => HttpResponse::Ok().json(snapshot),
+        Err(e) => e.response(),
+    }
+}
+
+#[get("/games/{id}")]
+async fn get_game(sessions: Data<Sessions>, id: Path<String>) -> impl Responder {
+    match sessions.with_game(&id, |game| Response::new(&id, game)) {
+        Ok(response) => HttpResponse::Ok().json(response),
+        Err(e) => e.response(),
+    }
+}
+
+#[post("/games/{id}")]
+async fn play_game(sessions: Data<Sessions>, id: Path<String>, data: Json<Move>) -> impl Responder {
+    println!("data -> {:?}", data);
+    play(&sessions, &id, data.0).await
+}
+
+#[delete("/games/{id}")]
+async fn delete_game(sessions: Data<Sessions>, id: Path<String>) -> impl Responder {
+    if sessions.remove(&id) {
+        HttpResponse::NoContent().finish()
+    } else {
+        HttpResponse::NotFound().finish()
+    }
+}
+
+/// Prints the starting board and runs the first solver for a new game.
+async fn setup(id: &str, game: Game, options: Options) -> Result<Response, BlockingError> {
+    game.print_status();
+
+    let mut response = Response::new(id, &game);
+    if game.flip == 0 {
+        response.middle = Some(web::block(move || solver::middle(&game, &options)).await?);
+    }
+
+    println!("{} turn", game.get_turn_name());
+    Ok(response)
+}
+
+/// A 500 for a solver that panicked. The game is left as it was after the
+/// selection.
+fn solver_failed(e: BlockingError) -> HttpResponse {
+    eprintln!("Solver failed: {}", e);
+    HttpResponse::InternalServerError().json(GameError {
+        error: "The solver failed on this position".to_string(),
+        suggestions: Vec::new(),
+    })
+}
+
+async fn play(sessions: &Sessions, id: &str, play_move: Move) -> HttpResponse {
+    let (selection, cancelled) = match play_move {
+        Move::Selection(selection) => (selection, false),
+        Move::CancelSelection {
             cancel: _,
-            selection: Selection { index, pillz, fury },
-        } => {
-            if let Some(game) = game.as_mut() {
-                game.clear_selection();
-                select(game, index, pillz, fury, true).await;
-            } else {
-                println!("{:?}", game);
+            selection,
+        } => (selection, true),
+    };
+
+    let played = sessions.with_session(id, |session| {
+        if cancelled {
+            if let Some((replay, _)) = &mut session.replay {
+                replay.cancel(&session.game);
             }
+            session.game.clear_selection();
         }
-    }
+        let played = select(&mut session.game, selection);
+        if let (Some(_), Some((replay, path))) = (&played, &mut session.replay) {
+            replay.push(selection);
+            if let Err(e) = replay.save(path) {
+                eprintln!("Failed to save replay: {}", e);
+            }
+        }
+        played.map(|played| (played, session.options))
+    });
 
-    HttpResponse::Ok()
+    match played {
+        Err(e) => e.response(),
+        Ok(None) => HttpResponse::BadRequest().finish(),
+        Ok(Some(((game, log), options))) => {
+            let mut response = Response::new(id, &game);
+            response.battle = log.as_ref().and_then(BattleResult::from_log);
+
+            if game.status() == GameStatus::Playing {
+                let (middle, solve) =
+                    match web::block(move || solve(&game, cancelled, &options)).await {
+                        Ok(solved) => solved,
+                        Err(e) => return solver_failed(e),
+                    };
+                response.middle = middle;
+                response.solve = solve;
+                println!("{} turn", game.get_turn_name());
+            }
+            HttpResponse::Ok().json(response)
+        }
+    }
 }
 
-async fn select(game: &mut Game, index: usize, pillz: u8, fury: bool, cancelled: bool) {
+/// Applies a selection and returns a copy of the game to solve along with the
+/// battle log, or `None` if the selection isn't allowed.
+fn select(game: &mut Game, selection: Selection) -> Option<(Game, Option<BattleLog>)> {
+    let Selection { index, pillz, fury } = selection;
     if !game.can_select(index, pillz, fury) {
-        return;
+        return None;
     }
 
     println!("Select {} {} {}", index, pillz, fury);
-    let battled = game.select(index, pillz, fury);
-    if !battled {
-        game.print_status();
-    }
-    if game.status() != GameStatus::Playing {
-        return;
+    let log = game.select(index, pillz, fury);
+    match &log {
+        Some(log) => print!("{}", log),
+        None => game.print_status(),
     }
+    Some((*game, log))
+}
 
+fn solve(
+    game: &Game,
+    cancelled: bool,
+    options: &Options,
+) -> (Option<MiddleResult>, Option<SelectionResult>) {
     let turn = game.get_turn();
 
-    let g = game.clone();
-    // actix_web::web::block(move || {
-    //     println!("Blocking");
-    if g.round == 0 {
+    if game.round == 0 {
         if !cancelled && turn == PlayerType::Player {
-            Solver::middle(&g);
+            return (Some(solver::middle(game, options)), None);
         }
+        (None, None)
     } else {
-        let best = Solver::solve(&g);
+        let best = solver::solve(game, options);
 
         match (best, turn) {
             (SelectionResult::Player(_), PlayerType::Opponent)
             | (SelectionResult::Opponent(_), PlayerType::Player) => {
-                Solver::middle(&g);
+                (Some(solver::middle(game, options)), Some(best))
+            }
+            (_, _) => {
+                println!("{:?}", best);
+                (None, Some(best))
             }
-            (_, _) => println!("{:?}", best),
         }
     }
-    //     println!("Unblocking");
-    // })
-    // .await
-    // .expect("Error in setup block");
+}
 
-    println!("{} turn", game.get_turn_name());
+/// Body returned by every game route.
+#[derive(Debug, Serialize)]
+struct Response {
+    id: String,
+    game: GameState,
+    /// The battle the last selection started, if it started one.
+    battle: Option<BattleResult>,
+    /// Win/draw/loss grid for the player to move, when the solver ran it.
+    middle: Option<MiddleResult>,
+    /// Forced result from the current position, when the solver ran it.
+    solve: Option<SelectionResult>,
+}
+
+impl Response {
+    fn new(id: &str, game: &Game) -> Self {
+        Response {
+            id: id.to_string(),
+            game: GameState::new(game),
+            battle: None,
+            middle: None,
+            solve: None,
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct GameState {
+    round: u8,
+    status: GameStatus,
+    turn: PlayerType,
+    day: bool,
+    life: [u8; 2],
+    pillz: [u8; 2],
+    cards: [[CardState; 4]; 2],
+}
+
+impl GameState {
+    fn new(game: &Game) -> Self {
+        GameState {
+            round: game.round,
+            status: game.status(),
+            turn: game.get_turn(),
+            day: game.day,
+            life: [game.p1.life, game.p2.life],
+            pillz: [game.p1.pillz, game.p2.pillz],
+            cards: [&game.h1, &game.h2].map(|hand| hand.cards.each_ref().map(CardState::new)),
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct CardState {
+    name: String,
+    level: u8,
+    power: u16,
+    damage: u16,
+    played: bool,
+    won: bool,
+}
+
+impl CardState {
+    fn new(card: &Card) -> Self {
+        CardState {
+            name: card.name().clone(),
+            level: card.level,
+            power: card.power.value,
+            damage: card.damage.value,
+            played: card.played,
+            won: card.won,
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct BattleResult {
+    winner: PlayerType,
+    /// Attack of the player's card, then the opponent's.
+    attack: [u16; 2],
+    /// Life the loser lost.
+    damage: u8,
+}
+
+impl BattleResult {
+    fn from_log(log: &BattleLog) -> Option<Self> {
+        let mut winner = None;
+        let mut attack = [0; 2];
+        let mut damage = 0;
+        for event in log.iter() {
+            match *event {
+                BattleEvent::Attack { player, attack: a } => {
+                    attack[(player == PlayerType::Opponent) as usize] = a;
+                }
+                BattleEvent::RoundWon { player } => winner = Some(player),
+                BattleEvent::Damage { amount, .. } => damage = amount,
+                _ => (),
+            }
+        }
+        Some(BattleResult {
+            winner: winner?,
+            attack,
+            damage,
+        })
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct NewGame {
+    cards: Vec<String>,
+    #[serde(default)]
+    flip: u8,
+    #[serde(default = "default_12")]
+    life: u8,
+    #[serde(default = "default_12")]
+    pillz: u8,
+    #[serde(default)]
+    night: bool,
+    /// Solver engine for this game, else the server's.
+    #[serde(default)]
+    solver: Option<Engine>,
+    /// Weights of the evaluation for this game, else the server's.
+    #[serde(default)]
+    eval: Option<Evaluation>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Resume {
+    snapshot: Snapshot,
+    /// Solver engine for this game, else the server's.
+    #[serde(default)]
+    solver: Option<Engine>,
+    /// Weights of the evaluation for this game, else the server's.
+    #[serde(default)]
+    eval: Option<Evaluation>,
+}
+
+/// Body of an error response: a game that can't be set up, or a solver
+/// that failed.
+#[derive(Debug, Serialize)]
+struct GameError {
+    error: String,
+    /// Closest card names, when a card name is unknown.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    suggestions: Vec<String>,
+}
+
+impl From<CardError> for GameError {
+    fn from(e: CardError) -> Self {
+        GameError {
+            error: e.to_string(),
+            suggestions: e.suggestions().to_vec(),
+        }
+    }
+}
+
+impl NewGame {
+    fn into_game(self) -> Result<Game, GameError> {
+        let cards = &self.cards;
+        if cards.len() != 8 {
+            return Err(GameError {
+                error: format!("Expected 8 cards, got {}", cards.len()),
+                suggestions: Vec::new(),
+            });
+        }
+        let hand =
+            |cards: &[String]| Hand::try_from_names([&cards[0], &cards[1], &cards[2], &cards[3]]);
+        let h1 = hand(&cards[..4])?;
+        let h2 = hand(&cards[4..])?;
+        if self.life == 0 || self.pillz > MAX_PILLZ {
+            return Err(GameError {
+                error: format!(
+                    "Life must be at least 1 and pillz at most {}, got {} and {}",
+                    MAX_PILLZ, self.life, self.pillz
+                ),
+                suggestions: Vec::new(),
+            });
+        }
+
+        let mut g = Game::new(h1, h2);
+        g.flip = self.flip;
+        g.day = !self.night;
+        g.p1.start_with(self.life, self.pillz);
+        g.p2.start_with(self.life, self.pillz);
+        Ok(g)
+    }
 }
 
 #[allow(dead_code)]
 #[derive(Debug, Deserialize)]
 #[serde(untagged)]
-enum Input {
-    Game {
-        cards: Vec<String>,
-        #[serde(default)]
-        flip: u8,
-        #[serde(default = "default_12")]
-        life: u8,
-        #[serde(default = "default_12")]
-        pillz: u8,
-    },
+enum Move {
     Selection(Selection),
-    // Cancel {
-    //     cancel: bool,
-    // },
-    CancelSelection {
-        cancel: bool,
-        selection: Selection,
-    },
+    CancelSelection { cancel: bool, selection: Selection },
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum Input {
+    Game(NewGame),
+    Move(Move),
 }
 
 fn default_12() -> u8 {
     12
 }
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        panic::{self, AssertUnwindSafe},
+        time::{Duration, Instant},
+    };
+
+    use super::{NewGame, Resume, SessionError, Sessions};
+    use crate::{
+        card::Hand,
+        game::Game,
+        solver::{Engine, Evaluation, Options},
+    };
+
+    fn game() -> Game {
+        let h1 = Hand::from_names("Figaro", "Elvis", "Danae", "Vivian");
+        let h2 = Hand::from_names("Cell", "John O Clock", "Hollow Spyke", "Dr Falkenstein");
+        Game::new(h1, h2)
+    }
+
+    #[test]
+    fn sessions_are_independent() {
+        let sessions = Sessions::new(Duration::from_secs(60), Options::default());
+        let a = sessions.create(game(), Options::default());
+        let b = sessions.create(game(), Options::default());
+        assert_ne!(a, b);
+
+        sessions
+            .with_game(&a, |game| game.select(0, 2, false))
+            .unwrap();
+        assert_eq!(
+            sessions.with_game(&a, |game| game.has_someone_selected()),
+            Ok(true)
+        );
+        assert_eq!(
+            sessions.with_game(&b, |game| game.has_someone_selected()),
+            Ok(false)
+        );
+
+        assert!(sessions.remove(&a));
+        assert!(!sessions.remove(&a));
+        assert_eq!(
+            sessions.with_game(&a, |game| game.round),
+            Err(SessionError::NotFound)
+        );
+        assert_eq!(sessions.len(), 1);
+    }
+
+    #[test]
+    fn sessions_expire() {
+        let sessions = Sessions::new(Duration::from_secs(60), Options::default());
+        let id = sessions.create(game(), Options::default());
+
+        sessions.expire(Instant::now());
+        assert_eq!(sessions.len(), 1);
+
+        sessions.expire(Instant::now() + Duration::from_secs(61));
+        assert_eq!(sessions.len(), 0);
+        assert_eq!(
+            sessions.with_game(&id, |game| game.round),
+            Err(SessionError::NotFound)
+        );
+    }
+
+    #[test]
+    fn poisoned_sessions_are_dropped() {
+        let sessions = Sessions::new(Duration::from_secs(60), Options::default());
+        let poison = |id: &str| {
+            let panicked = panic::catch_unwind(AssertUnwindSafe(|| {
+                sessions.with_game(id, |_| panic!("selection failed"))
+            }));
+            assert!(panicked.is_err());
+        };
+
+        let id = sessions.create(game(), Options::default());
+        poison(&id);
+        assert_eq!(
+            sessions.with_game(&id, |game| game.round),
+            Err(SessionError::Poisoned)
+        );
+        assert_eq!(
+            sessions.with_game(&id, |game| game.round),
+            Err(SessionError::NotFound)
+        );
+
+        // Even when they're not used again
+        sessions.create(game(), Options::default());
+        poison(&sessions.create(game(), Options::default()));
+        sessions.expire(Instant::now());
+        assert_eq!(sessions.len(), 1);
+    }
+
+    #[test]
+    fn games_default_to_the_server_options() {
+        let eval: Evaluation = "life".parse().unwrap();
+        let options = Options {
+            eval,
+            ..Options::new(Engine::Tree)
+        };
+        let sessions = Sessions::new(Duration::from_secs(60), options);
+        assert_eq!(sessions.options(None, None), options);
+
+        let own = Evaluation::default();
+        let game = sessions.options(Some(Engine::AlphaBeta), Some(own));
+        assert_eq!(game, Options::new(Engine::AlphaBeta));
+    }
+
+    #[test]
+    fn snapshots_resume_sessions() {
+        let sessions = Sessions::new(Duration::from_secs(60), Options::default());
+        let id = sessions.create(game(), Options::default());
+        sessions
+            .with_game(&id, |game| {
+                game.select(0, 2, false);
+                game.select(0, 1, false);
+            })
+            .unwrap();
+        let snapshot = sessions.with_game(&id, |game| game.snapshot()).unwrap();
+
+        let body = format!(
+            r#"{{ "snapshot": {}, "solver": "tree" }}"#,
+            snapshot.to_json()
+        );
+        let resume: Resume = serde_json::from_str(&body).unwrap();
+        assert_eq!(resume.solver, Some(Engine::Tree));
+        let resumed = sessions.create(
+            Game::from_snapshot(resume.snapshot).unwrap(),
+            Options::default(),
+        );
+        assert_eq!(
+            sessions.with_game(&resumed, |game| game.snapshot().to_json()),
+            Ok(snapshot.to_json())
+        );
+
+        // Replays start from the first round
+        assert_eq!(
+            sessions.with_session(&id, |session| session.replay.is_some()),
+            Ok(true)
+        );
+        assert_eq!(
+            sessions.with_session(&resumed, |session| session.replay.is_some()),
+            Ok(false)
+        );
+    }
+
+    #[test]
+    fn unknown_cards_are_rejected() {
+        let new_game = |cards: &[&str]| NewGame {
+            cards: cards.iter().map(|name| name.to_string()).collect(),
+            flip: 0,
+            life: 12,
+            pillz: 12,
+            night: false,
+            solver: None,
+            eval: None,
+        };
+        let hand = ["Figaro", "Elvis", "Danae", "Vivian"];
+        assert!(new_game(&[hand, hand].concat()).into_game().is_ok());
+        assert!(new_game(&hand).into_game().is_err());
+
+        let error = new_game(&[hand, ["Cel", "Elvis", "Danae", "Vivian"]].concat())
+            .into_game()
+            .unwrap_err();
+        assert!(error.suggestions.contains(&"Cell".to_string()));
+
+        let error = new_game(&[hand, ["Figaro:9", "Elvis", "Danae", "Vivian"]].concat())
+            .into_game()
+            .unwrap_err();
+        assert!(error.error.starts_with("Figaro can't be level 9"));
+    }
+
+    #[test]
+    fn out_of_range_life_and_pillz_are_rejected() {
+        let hand = ["Figaro", "Elvis", "Danae", "Vivian"];
+        let new_game = |life, pillz| NewGame {
+            cards: [hand, hand]
+                .concat()
+                .iter()
+                .map(|name| name.to_string())
+                .collect(),
+            flip: 0,
+            life,
+            pillz,
+            night: false,
+            solver: None,
+            eval: None,
+        };
+        assert!(new_game(1, 31).into_game().is_ok());
+        assert!(new_game(0, 12).into_game().is_err());
+        assert!(new_game(12, 40).into_game().is_err());
+    }
+}
@@ -0,0 +1,275 @@
+use std::{
+    fmt::{Display, Formatter},
+    fs, io,
+    path::Path,
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    battle::Events,
+    card::Hand,
+    game::{Game, Player, Selection},
+    output::Terminal,
+};
+
+/// Bumped whenever the snapshot layout changes.
+pub const SNAPSHOT_VERSION: u32 = 1;
+
+/// Everything needed to resume a `Game` exactly, including pending global
+/// events. Saved as JSON or as compact CBOR bytes.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Snapshot {
+    pub version: u32,
+    pub round: u8,
+    pub p1: Player,
+    pub p2: Player,
+    pub h1: Hand,
+    pub h2: Hand,
+    pub s1: Option<Selection>,
+    pub s2: Option<Selection>,
+    pub events1: Events,
+    pub events2: Events,
+    pub flip: u8,
+    pub day: bool,
+}
+
+#[derive(Debug)]
+pub enum SnapshotError {
+    Io(io::Error),
+    Json(serde_json::Error),
+    Binary(String),
+    /// The snapshot was written by a different snapshot version.
+    Version(u32),
+}
+
+impl Display for SnapshotError {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        match self {
+            SnapshotError::Io(e) => write!(f, "Can't read snapshot: {}", e),
+            SnapshotError::Json(e) => write!(f, "Invalid JSON snapshot: {}", e),
+            SnapshotError::Binary(e) => write!(f, "Invalid binary snapshot: {}", e),
+            SnapshotError::Version(v) => write!(
+                f,
+                "Unsupported snapshot version {} (expected {})",
+                v, SNAPSHOT_VERSION
+            ),
+        }
+    }
+}
+
+impl std::error::Error for SnapshotError {}
+
+impl Snapshot {
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).expect("Snapshot is always valid JSON")
+    }
+    pub fn from_json(json: &str) -> Result<Self, SnapshotError> {
+        serde_json::from_str::<Snapshot>(json)
+            .map_err(SnapshotError::Json)?
+            .checked()
+    }
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        ciborium::into_writer(self, &mut bytes).expect("Snapshot is always valid CBOR");
+        bytes
+    }
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, SnapshotError> {
+        ciborium::from_reader::<Snapshot, _>(bytes)
+            .map_err(|e| SnapshotError::Binary(e.to_string()))?
+            .checked()
+    }
+
+    /// Writes JSON to a `.json` file, CBOR to any other.
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        if path.extension().is_some_and(|ext| ext == "json") {
+            fs::write(path, self.to_json())
+        } else {
+            fs::write(path, self.to_bytes())
+        }
+    }
+    /// Reads a snapshot saved by `save`, telling JSON and CBOR apart by
+    /// their first byte.
+    pub fn load(path: &Path) -> Result<Self, SnapshotError> {
+        let bytes = fs::read(path).map_err(SnapshotError::Io)?;
+        match bytes.iter().find(|b| !b.is_ascii_whitespace()) {
+            Some(b'{') => {
+                let json = String::from_utf8_lossy(&bytes);
+                Snapshot::from_json(&json)
+            }
+            _ => Snapshot::from_bytes(&bytes),
+        }
+    }
+
+    fn checked(self) -> Result<Self, SnapshotError> {
+        if self.version == SNAPSHOT_VERSION {
+            Ok(self)
+        } else {
+            Err(SnapshotError::Version(self.version))
+        }
+    }
+}
+
+impl Game {
+    pub fn snapshot(&self) -> Snapshot {
+        Snapshot {
+            version: SNAPSHOT_VERSION,
+            round: self.round,
+            p1: self.p1,
+            p2: self.p2,
+            h1: self.h1,
+            h2: self.h2,
+            s1: self.s1,
+            s2: self.s2,
+            events1: self.events1,
+            events2: self.events2,
+            flip: self.flip,
+            day: self.day,
+        }
+    }
+
+    /// Restores a game from a snapshot. The restored game prints to the
+    /// terminal.
+    pub fn from_snapshot(snapshot: Snapshot) -> Result<Self, SnapshotError> {
+        let s = snapshot.checked()?;
+        Ok(Game {
+            round: s.round,
+            p1: s.p1,
+            p2: s.p2,
+            h1: s.h1,
+            h2: s.h2,
+            s1: s.s1,
+            s2: s.s2,
+            events1: s.events1,
+            events2: s.events2,
+            flip: s.flip,
+            day: s.day,
+            output: &Terminal,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, fs};
+
+    use super::{Snapshot, SnapshotError, SNAPSHOT_VERSION};
+    use crate::{
+        ability::{Ability, Condition, ABILITIES},
+        card::Hand,
+        game::{Game, GameStatus},
+        output::{Silent, Terminal},
+    };
+
+    fn game() -> Game {
+        // Hugo and Mr Big Duke are leaders, so both sides have global events
+        let h1 = Hand::from_names("Hugo", "Elvis", "Danae", "Vivian");
+        let h2 = Hand::from_names("Mr Big Duke", "John O Clock", "Hollow Spyke", "Cell");
+        let mut game = Game::new(h1, h2);
+        game.output = &Silent;
+        game
+    }
+
+    /// Plays the rest of the game in both copies and checks every battle and
+    /// the final state match.
+    fn assert_same_future(mut a: Game, mut b: Game) {
+        a.output = &Terminal;
+        b.output = &Terminal;
+        let mut turn = 0;
+        while a.status() == GameStatus::Playing {
+            let index = (turn..turn + 4)
+                .map(|i| i % 4)
+                .find(|&i| !a.get_turn_hand()[i].played)
+                .unwrap();
+            let pillz = (turn as u8 * 2).min(a.get_turn_player().pillz);
+
+            let log = a.select(index, pillz, false);
+            assert_eq!(log, b.select(index, pillz, false));
+            assert_eq!(a.snapshot().to_json(), b.snapshot().to_json());
+            turn += 1;
+        }
+        assert_eq!(b.status(), a.status());
+    }
+
+    #[test]
+    fn json_round_trip() {
+        let mut game = game();
+        game.select(0, 3, false);
+        game.select(0, 1, false);
+        game.select(1, 2, false);
+
+        let json = game.snapshot().to_json();
+        let restored = Game::from_snapshot(Snapshot::from_json(&json).unwrap()).unwrap();
+        assert_eq!(restored.snapshot().to_json(), json);
+
+        assert_same_future(game, restored);
+    }
+
+    #[test]
+    fn binary_round_trip() {
+        let mut game = game();
+        game.day = false;
+        game.select(0, 3, false);
+        game.select(0, 1, false);
+
+        let bytes = game.snapshot().to_bytes();
+        assert!(bytes.len() < game.snapshot().to_json().len());
+        let restored = Game::from_snapshot(Snapshot::from_bytes(&bytes).unwrap()).unwrap();
+        assert_eq!(restored.snapshot().to_bytes(), bytes);
+
+        assert_same_future(game, restored);
+    }
+
+    #[test]
+    fn file_round_trip() {
+        let mut game = game();
+        game.select(0, 3, false);
+
+        for name in ["urban_snapshot.json", "urban_snapshot.cbor"] {
+            let path = env::temp_dir().join(name);
+            game.snapshot().save(&path).unwrap();
+            let loaded = Snapshot::load(&path).unwrap();
+            fs::remove_file(&path).unwrap();
+            assert_eq!(loaded.to_json(), game.snapshot().to_json());
+        }
+        assert!(matches!(
+            Snapshot::load(&env::temp_dir().join("urban_snapshot_missing.json")),
+            Err(SnapshotError::Io(_))
+        ));
+    }
+
+    #[test]
+    fn rejects_other_versions() {
+        let mut snapshot = game().snapshot();
+        snapshot.version = SNAPSHOT_VERSION + 1;
+
+        assert!(matches!(
+            Snapshot::from_json(&snapshot.to_json()),
+            Err(SnapshotError::Version(_))
+        ));
+        assert!(matches!(
+            Game::from_snapshot(snapshot),
+            Err(SnapshotError::Version(_))
+        ));
+    }
+
+    #[test]
+    fn clan_conditions_round_trip() {
+        let abilities = ABILITIES.values().filter(|ability| {
+            ability.conditions.iter().any(|c| {
+                matches!(
+                    c,
+                    Some(Condition::Infiltrate(_)) | Some(Condition::Versus(_))
+                )
+            })
+        });
+
+        let mut count = 0;
+        for ability in abilities {
+            let json = serde_json::to_string(ability).unwrap();
+            assert_eq!(&serde_json::from_str::<Ability>(&json).unwrap(), ability);
+            count += 1;
+        }
+        assert!(count > 0);
+    }
+}
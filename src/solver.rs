@@ -1,22 +1,32 @@
-use core::sync::atomic::Ordering;
 use std::{
     fmt::Display,
     io::{stdout, Write},
-    slice::Iter,
-    time::Instant,
+    sync::atomic::{AtomicU32, Ordering},
+    time::{Duration, Instant},
 };
 
+use clap::ValueEnum;
 use colored::{Color, Colorize};
-use lazy_static::lazy_static;
 use rayon::prelude::{IntoParallelIterator, ParallelIterator};
+use serde::{Deserialize, Serialize};
 
 use crate::{
-    ability, battle,
-    game::{self, Game, GameStatus, PlayerType, Selection, BATTLE_COUNT},
-    modifiers,
+    game::{Game, GameStatus, PlayerType, Selection},
+    output::Silent,
 };
 
-pub struct Solver {}
+use self::ranges::{shift_false_range, split_range, split_shift_range};
+pub use self::{
+    alphabeta::AlphaBeta, eval::Evaluation, minimax::Minimax, table::Table, tree::Tree,
+};
+
+mod alphabeta;
+mod eval;
+mod minimax;
+mod nash;
+mod ranges;
+mod table;
+pub mod tree;
 
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum GameResult {
@@ -25,13 +35,23 @@ pub enum GameResult {
     Lose,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
 pub enum SelectionResult {
     Player(Selection),
     Draw(Selection),
     Opponent(Selection),
 }
 
+impl SelectionResult {
+    pub fn selection(&self) -> &Selection {
+        match self {
+            SelectionResult::Player(s) => s,
+            SelectionResult::Draw(s) => s,
+            SelectionResult::Opponent(s) => s,
+        }
+    }
+}
+
 impl Display for SelectionResult {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(
@@ -52,253 +72,673 @@ impl Display for SelectionResult {
     }
 }
 
-pub fn toggle_print() {
-    unsafe {
-        ability::PRINT = !ability::PRINT;
-        game::PRINT = !game::PRINT;
-        modifiers::PRINT = !modifiers::PRINT;
-        battle::PRINT = !battle::PRINT;
-    }
+/// Wins, draws and losses for one selection over every reply the opponent has.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct Outcome {
+    pub selection: Selection,
+    pub wins: u8,
+    pub draws: u8,
+    pub losses: u8,
+    /// Average evaluation over the replies, from the side of the player to
+    /// move. 0 without an evaluation.
+    pub eval: f32,
+}
+
+/// The grid `Solver::middle` prints, plus its pick.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct MiddleResult {
+    pub outcomes: Vec<Outcome>,
+    pub best: Selection,
+    pub rate: f32,
+    pub battles: u32,
 }
 
-impl Solver {
-    pub fn middle(game: &Game) {
-        let battle_count = unsafe { BATTLE_COUNT.load(Ordering::Relaxed) };
-        toggle_print();
-        let now = Instant::now();
+/// A selection and how often to play it.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct Choice {
+    pub selection: Selection,
+    pub probability: f32,
+}
+
+/// Optimal mixed strategy for the player to move, from `Solver::mixed`.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Strategy {
+    /// Average result for the player to move, from -1 for a sure loss to 1
+    /// for a sure win.
+    pub value: f32,
+    /// The selections to pick from, with their probabilities.
+    pub choices: Vec<Choice>,
+}
+
+/// A way of searching the game tree.
+///
+/// Engines only have to solve positions where the player to move has seen
+/// every earlier selection. The grid of `middle` and the hidden pillz of a
+/// pending selection in `solve` are handled on top of that, unless an engine
+/// overrides them.
+pub trait Solver: Sync {
+    /// The forced result when both players play perfectly from here, the
+    /// selection of the player to move that gets it, and the evaluation of
+    /// the game it leads to from the player's side. With an evaluation,
+    /// results are compared first, then evaluations. Without one, the
+    /// evaluation is 0.
+    fn value(&self, game: &Game, search: &Search) -> (SelectionResult, f32);
+
+    /// `value` without the evaluation.
+    fn best(&self, game: &Game, search: &Search) -> SelectionResult {
+        self.value(game, search).0
+    }
+
+    /// Like `best`, but the opponent's pending selection, if any, only shows
+    /// its card: the worst case over its pillz and fury is assumed.
+    fn solve(&self, game: &Game, search: &Search) -> SelectionResult {
+        if game.s1.is_none() != game.s2.is_none() {
+            solve_second(self, game, search)
+        } else {
+            self.best(game, search)
+        }
+    }
+
+    /// The pillz and fury of the first selection are hidden from the second
+    /// player, so each round is a matrix game between every selection and
+    /// every reply, the later rounds valued as matrix games in turn. The
+    /// equilibrium strategy of the player to move and its value.
+    ///
+    /// No engine searches with hidden pillz, so all engines share this one.
+    fn mixed(&self, game: &Game, search: &Search) -> Strategy {
+        if game.s1.is_none() != game.s2.is_none() {
+            nash::second(game, search)
+        } else {
+            nash::first(game, search)
+        }
+    }
+
+    /// Wins, draws and losses of every selection of the player to move over
+    /// every reply, the best rate and the selection that gets it.
+    fn middle(&self, game: &Game, search: &Search) -> (f32, Selection, Vec<Outcome>) {
         if game.s1.is_some() || game.s2.is_some() {
             if game.round == 0 {
-                Solver::middle_second_par(game);
+                middle_second_par(self, game, search)
             } else {
-                Solver::middle_second(game);
+                middle_second(self, game, search)
             }
         } else if game.round == 0 {
-            Solver::middle_first_par(game);
+            middle_first_par(self, game, search)
         } else {
-            Solver::middle_first(game);
+            middle_first(self, game, search)
+        }
+    }
+}
+
+/// The solver engines, selectable with `--solver` or the `solver` field of
+/// a new game.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum Engine {
+    /// Depth-first search stopping at the first forced win.
+    #[default]
+    Minimax,
+    /// Builds the whole tree of selections before reading the result.
+    Tree,
+    /// Alpha-beta search, trying the selections that cut branches before
+    /// first.
+    #[value(name = "alphabeta")]
+    AlphaBeta,
+}
+
+impl Engine {
+    pub fn solver(self) -> &'static dyn Solver {
+        match self {
+            Engine::Minimax => &Minimax,
+            Engine::Tree => &Tree,
+            Engine::AlphaBeta => &AlphaBeta,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Options {
+    pub engine: Engine,
+    /// Breaks ties between selections with the same result.
+    pub eval: Evaluation,
+}
+
+impl Options {
+    pub fn new(engine: Engine) -> Self {
+        Options {
+            engine,
+            eval: Evaluation::default(),
+        }
+    }
+}
+
+/// State shared by every branch of one search.
+#[derive(Default)]
+pub struct Search {
+    battles: AtomicU32,
+    /// Positions solved so far by `Solver::best`.
+    pub table: Table,
+    /// Values of positions between rounds solved by the mixed strategies.
+    pub values: Table<f64>,
+    pub eval: Evaluation,
+}
+
+impl Search {
+    pub fn new(eval: Evaluation) -> Self {
+        Search {
+            eval,
+            ..Default::default()
+        }
+    }
+
+    #[inline]
+    pub fn count_battle(&self) {
+        self.battles.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// The result of `game` from the table, else `solve`'s, which is then
+    /// stored for the next time the position comes up.
+    pub fn cached(&self, game: &Game, solve: impl FnOnce() -> SelectionResult) -> SelectionResult {
+        let key = game.position_hash();
+        if let Some(result) = self.table.get(key) {
+            return result;
         }
-        toggle_print();
-        let battles: u32 = unsafe { BATTLE_COUNT.load(Ordering::Relaxed) } - battle_count;
-        let elapsed = now.elapsed();
+        let result = solve();
+        self.table.insert(key, result);
+        result
+    }
+
+    pub fn battles(&self) -> u32 {
+        self.battles.load(Ordering::Relaxed)
+    }
+
+    fn print(&self, elapsed: Duration) {
+        let battles = self.battles();
+        let hits = self.table.hits() + self.values.hits();
+        let misses = self.table.misses() + self.values.misses();
         println!(
-            "{} {} /{:.1?}secs  ({:.0?}k/s)",
+            "{} {} /{:.1?}secs ({:.0?}k/s)  {} {} hits {} misses ({:.1?}%)",
             " Battle Count ".white().on_bright_purple(),
             battles,
             elapsed.as_secs_f32(),
-            battles as f32 / elapsed.as_secs_f32() / 1000f32
+            battles as f32 / elapsed.as_secs_f32() / 1000f32,
+            " Table ".white().on_bright_purple(),
+            hits,
+            misses,
+            hits as f32 / (hits + misses).max(1) as f32 * 100f32
         );
     }
+}
 
-    fn print_count(pillz: u8, fury: bool, wins: u8, draws: u8, losses: u8) {
-        let rate = (wins + draws) as f32 / (wins + draws + losses) as f32;
-        if losses == 0 {
-            if draws == 0 {
-                print!("{} ", pillz.to_string().black().on_green());
-            } else {
-                print!("{} ", "d".bright_yellow());
-            }
-        } else if wins + draws > losses {
-            if wins == 0 {
-                print!("{} ", "d".bright_yellow());
-            } else {
-                print!(
-                    "{} ",
-                    format!("{:X}", pillz).color(if fury { Color::Red } else { Color::Green })
-                )
-            }
-        } else if rate <= 0.25 {
-            print!("{} ", "x".bright_black())
-        } else if wins + draws <= losses {
-            print!("{} ", format!("{:X}", pillz).bright_black())
+/// The solvers' view of a game: the same position, printing nothing.
+fn silent(game: &Game) -> Game {
+    let mut game = *game;
+    game.output = &Silent;
+    game
+}
+
+/// Win/draw/loss grid of every selection for the player to move, printed as
+/// it's computed, and the pick with the best rate.
+pub fn middle(game: &Game, options: &Options) -> MiddleResult {
+    let search = Search::new(options.eval);
+    let now = Instant::now();
+    let (rate, best, outcomes) = options.engine.solver().middle(&silent(game), &search);
+    search.print(now.elapsed());
+
+    MiddleResult {
+        outcomes,
+        best,
+        rate,
+        battles: search.battles(),
+    }
+}
+
+/// Forced result from the position for the player to move.
+pub fn solve(game: &Game, options: &Options) -> SelectionResult {
+    let search = Search::new(options.eval);
+    let now = Instant::now();
+    let best = options.engine.solver().solve(&silent(game), &search);
+    search.print(now.elapsed());
+
+    if !options.eval.is_none() && game.s1.is_none() == game.s2.is_none() {
+        let (_, eval) = options.engine.solver().value(&silent(game), &search);
+        let eval = if game.get_turn() == PlayerType::Player {
+            eval
         } else {
-            println!("({}, {}, {})", wins, losses, draws);
+            -eval
+        };
+        println!("{} {:.1}", " Eval ".black().on_bright_green(), eval);
+    }
+    best
+}
+
+/// Optimal mixed strategy for the player to move, see `Solver::mixed`.
+pub fn mixed(game: &Game, options: &Options) -> Strategy {
+    let search = Search::new(options.eval);
+    let now = Instant::now();
+    let strategy = options.engine.solver().mixed(&silent(game), &search);
+    search.print(now.elapsed());
+    strategy
+}
+
+/// The pick for whoever moves next: a forced win or draw if there is one,
+/// otherwise the best rate from `middle`.
+pub fn recommend(game: &Game, options: &Options) -> Selection {
+    if game.round != 0 {
+        match (solve(game, options), game.get_turn()) {
+            (SelectionResult::Player(_), PlayerType::Opponent)
+            | (SelectionResult::Opponent(_), PlayerType::Player) => (),
+            (best, _) => return *best.selection(),
         }
-        stdout().flush().unwrap();
     }
+    middle(game, options).best
+}
+
+/// Result of a game after a battle, once played out, and its evaluation from
+/// the player's side.
+fn outcome<S: Solver + ?Sized>(solver: &S, game: &Game, search: &Search) -> (GameStatus, f32) {
+    match game.status() {
+        GameStatus::Playing => match solver.value(game, search) {
+            (SelectionResult::Player(_), eval) => (GameStatus::Player, eval),
+            (SelectionResult::Draw(_), eval) => (GameStatus::Draw, eval),
+            (SelectionResult::Opponent(_), eval) => (GameStatus::Opponent, eval),
+        },
+        status => (status, search.eval.score(game)),
+    }
+}
+
+/// The evaluation after a rate, when there is one.
+fn eval_note(search: &Search, eval: f32) -> String {
+    if search.eval.is_none() {
+        String::new()
+    } else {
+        format!(" eval {:.1}", eval)
+    }
+}
 
-    fn middle_second(game: &Game) {
-        let i = if game.s1.is_none() {
-            game.s2.unwrap().index
+fn print_count(pillz: u8, fury: bool, wins: u8, draws: u8, losses: u8) {
+    let rate = (wins + draws) as f32 / (wins + draws + losses) as f32;
+    if losses == 0 {
+        if draws == 0 {
+            print!("{} ", pillz.to_string().black().on_green());
         } else {
-            game.s1.unwrap().index
-        };
+            print!("{} ", "d".bright_yellow());
+        }
+    } else if wins + draws > losses {
+        if wins == 0 {
+            print!("{} ", "d".bright_yellow());
+        } else {
+            print!(
+                "{} ",
+                format!("{:X}", pillz).color(if fury { Color::Red } else { Color::Green })
+            )
+        }
+    } else if rate <= 0.25 {
+        print!("{} ", "x".bright_black())
+    } else if wins + draws <= losses {
+        print!("{} ", format!("{:X}", pillz).bright_black())
+    } else {
+        println!("({}, {}, {})", wins, losses, draws);
+    }
+    stdout().flush().unwrap();
+}
 
-        let pillz1 = game.get_turn_opponent().pillz;
-        let pillz2 = game.get_turn_player().pillz;
+fn middle_second<S: Solver + ?Sized>(
+    solver: &S,
+    game: &Game,
+    search: &Search,
+) -> (f32, Selection, Vec<Outcome>) {
+    let i = if game.s1.is_none() {
+        game.s2.unwrap().index
+    } else {
+        game.s1.unwrap().index
+    };
 
-        let turn = game.get_turn();
-        let hand = game.get_turn_hand();
+    let pillz1 = game.get_turn_opponent().pillz;
+    let pillz2 = game.get_turn_player().pillz;
 
-        let mut game = game.clone();
-        game.clear_selection();
+    let turn = game.get_turn();
+    let hand = game.get_turn_hand();
 
-        let mut best_pillz = 0;
-        let mut best_rate = 0f32;
-        let mut best_rate_rounded = 0u32;
-        let mut best_selection = Selection::default();
+    let mut game = game.clone();
+    game.clear_selection();
 
-        for index in 0..4 {
-            if hand[index].played {
-                continue;
+    let mut best_pillz = 0;
+    let mut best_rate = 0f32;
+    let mut best_rate_rounded = 0u32;
+    let mut best_eval = f32::NEG_INFINITY;
+    let mut best_selection = Selection::default();
+    let mut outcomes = Vec::new();
+
+    for index in 0..4 {
+        if hand[index].played {
+            continue;
+        }
+
+        for &(pillz, fury) in shift_false_range(pillz2, game.round) {
+            let mut p_wins = 0u8;
+            let mut draws = 0u8;
+            let mut o_wins = 0u8;
+            let mut total_eval = 0f32;
+
+            for &(p, f) in split_range(pillz1) {
+                let mut g = game.clone();
+                g.select(i, p, f);
+                g.select(index, pillz, fury);
+                search.count_battle();
+
+                let (status, eval) = outcome(solver, &g, search);
+                match status {
+                    GameStatus::Player => p_wins += 1,
+                    GameStatus::Draw => draws += 1,
+                    _ => o_wins += 1,
+                }
+                total_eval += eval;
+            }
+            let (wins, losses) = if turn == PlayerType::Player {
+                (p_wins, o_wins)
+            } else {
+                (o_wins, p_wins)
+            };
+            let eval = if turn == PlayerType::Player {
+                total_eval
+            } else {
+                -total_eval
+            } / (wins + draws + losses).max(1) as f32;
+
+            let rate = (wins + draws) as f32 / (wins + draws + losses) as f32;
+            let rate_rounded = (rate * 100f32) as u32 / 10;
+            if rate_rounded > best_rate_rounded
+                || (rate_rounded == best_rate_rounded
+                    && (eval > best_eval || (eval == best_eval && pillz < best_pillz)))
+            {
+                best_pillz = pillz;
+                best_rate = rate;
+                best_rate_rounded = rate_rounded;
+                best_eval = eval;
+                best_selection = Selection::new(index, pillz, fury);
             }
 
+            print_count(pillz, fury, wins, draws, losses);
+            outcomes.push(Outcome {
+                selection: Selection::new(index, pillz, fury),
+                wins,
+                draws,
+                losses,
+                eval,
+            });
+        }
+        // println!();
+        println!(
+            "({:.1?}%) {}{}",
+            best_rate * 100f32,
+            best_selection,
+            eval_note(search, best_eval)
+        );
+    }
+
+    println!(
+        "({:.1?}%) {}{}",
+        best_rate * 100f32,
+        best_selection,
+        eval_note(search, best_eval)
+    );
+    (best_rate, best_selection, outcomes)
+}
+
+fn middle_second_par<S: Solver + ?Sized>(
+    solver: &S,
+    game: &Game,
+    search: &Search,
+) -> (f32, Selection, Vec<Outcome>) {
+    let i = if game.s1.is_none() {
+        game.s2.unwrap().index
+    } else {
+        game.s1.unwrap().index
+    };
+
+    let turn = game.get_turn();
+    let pillz1 = game.get_turn_opponent().pillz;
+    let pillz2 = game.get_turn_player().pillz;
+
+    let mut game = game.clone();
+    game.clear_selection();
+
+    let cards = (0..4)
+        // .filter(|&index| !game.get_turn_hand().index(index).played)
+        // .collect::<Vec<usize>>()
+        .into_par_iter()
+        .map(|index| {
+            let mut best_pillz = 0;
+            let mut best_rate = 0f32;
+            let mut best_rate_rounded = 0u32;
+            let mut best_eval = f32::NEG_INFINITY;
+            let mut best_selection = Selection::default();
+            let mut outcomes = Vec::new();
+
             for &(pillz, fury) in shift_false_range(pillz2, game.round) {
                 let mut p_wins = 0u8;
                 let mut draws = 0u8;
                 let mut o_wins = 0u8;
+                let mut total_eval = 0f32;
 
                 for &(p, f) in split_range(pillz1) {
+                    if p == 0 {
+                        continue;
+                    }
                     let mut g = game.clone();
                     g.select(i, p, f);
                     g.select(index, pillz, fury);
+                    search.count_battle();
 
-                    match g.status() {
+                    let (status, eval) = outcome(solver, &g, search);
+                    match status {
                         GameStatus::Player => p_wins += 1,
                         GameStatus::Draw => draws += 1,
-                        GameStatus::Opponent => o_wins += 1,
-                        GameStatus::Playing => {
-                            let best = Solver::solve_first(&g);
-                            match best {
-                                SelectionResult::Player(_) => p_wins += 1,
-                                SelectionResult::Draw(_) => draws += 1,
-                                SelectionResult::Opponent(_) => o_wins += 1,
-                            }
-                        }
+                        _ => o_wins += 1,
                     }
+                    total_eval += eval;
                 }
                 let (wins, losses) = if turn == PlayerType::Player {
                     (p_wins, o_wins)
                 } else {
                     (o_wins, p_wins)
                 };
+                let eval = if turn == PlayerType::Player {
+                    total_eval
+                } else {
+                    -total_eval
+                } / (wins + draws + losses).max(1) as f32;
 
                 let rate = (wins + draws) as f32 / (wins + draws + losses) as f32;
                 let rate_rounded = (rate * 100f32) as u32 / 10;
                 if rate_rounded > best_rate_rounded
-                    || (rate_rounded == best_rate_rounded && pillz < best_pillz)
+                    || (rate_rounded == best_rate_rounded
+                        && (eval > best_eval || (eval == best_eval && pillz < best_pillz)))
                 {
                     best_pillz = pillz;
                     best_rate = rate;
                     best_rate_rounded = rate_rounded;
+                    best_eval = eval;
                     best_selection = Selection::new(index, pillz, fury);
                 }
 
-                Solver::print_count(pillz, fury, wins, draws, losses);
+                print_count(pillz, fury, wins, draws, losses);
+                outcomes.push(Outcome {
+                    selection: Selection::new(index, pillz, fury),
+                    wins,
+                    draws,
+                    losses,
+                    eval,
+                });
             }
             // println!();
-            println!("({:.1?}%) {}", best_rate * 100f32, best_selection);
+            println!(
+                "\n({:.1?}%) {}{}",
+                best_rate * 100f32,
+                best_selection,
+                eval_note(search, best_eval)
+            );
+
+            (
+                best_rate,
+                best_selection,
+                best_rate_rounded,
+                best_eval,
+                outcomes,
+            )
+        })
+        .collect::<Vec<_>>();
+
+    let &(best_rate, best_selection, .., best_eval, _) = cards
+        .iter()
+        .max_by(|&&(_, a, a_rate, a_eval, _), &&(_, b, b_rate, b_eval, _)| {
+            a_rate
+                .cmp(&b_rate)
+                .then(a_eval.total_cmp(&b_eval))
+                .then(b.pillz.cmp(&a.pillz))
+        })
+        .unwrap();
+
+    println!(
+        "{}{}{}",
+        format!(" {:.1?}% ", best_rate * 100f32).black().on_green(),
+        format!(" {} ", best_selection).green(),
+        eval_note(search, best_eval)
+    );
+    let outcomes = cards.into_iter().flat_map(|card| card.4).collect();
+    (best_rate, best_selection, outcomes)
+}
+
+fn middle_first<S: Solver + ?Sized>(
+    solver: &S,
+    game: &Game,
+    search: &Search,
+) -> (f32, Selection, Vec<Outcome>) {
+    let pillz1 = game.get_turn_player().pillz;
+    let pillz2 = game.get_turn_opponent().pillz;
+
+    let turn = game.get_turn();
+    let hand1 = game.get_turn_hand();
+    let hand2 = game.get_turn_opponent_hand();
+
+    let mut best_pillz = 0;
+    let mut best_rate = 0f32;
+    let mut best_rate_rounded = 0u32;
+    let mut best_eval = f32::NEG_INFINITY;
+    let mut best_selection = Selection::default();
+    let mut outcomes = Vec::new();
+
+    for index in 0..4 {
+        if hand1[index].played {
+            continue;
         }
 
-        println!("({:.1?}%) {}", best_rate * 100f32, best_selection);
-    }
+        for &(pillz, fury) in shift_false_range(pillz1, game.round) {
+            let mut p_wins = 0;
+            let mut draws = 0;
+            let mut o_wins = 0;
+            let mut total_eval = 0f32;
 
-    fn middle_second_par(game: &Game) {
-        let i = if game.s1.is_none() {
-            game.s2.unwrap().index
-        } else {
-            game.s1.unwrap().index
-        };
+            for i in 0..4 {
+                if hand2[i].played {
+                    continue;
+                }
 
-        let turn = game.get_turn();
-        let pillz1 = game.get_turn_opponent().pillz;
-        let pillz2 = game.get_turn_player().pillz;
-
-        let mut game = game.clone();
-        game.clear_selection();
-
-        let (best_rate, best_selection, ..) = (0..4)
-            // .filter(|&index| !game.get_turn_hand().index(index).played)
-            // .collect::<Vec<usize>>()
-            .into_par_iter()
-            .map(|index| {
-                let mut best_pillz = 0;
-                let mut best_rate = 0f32;
-                let mut best_rate_rounded = 0u32;
-                let mut best_selection = Selection::default();
-
-                for &(pillz, fury) in shift_false_range(pillz2, game.round) {
-                    let mut p_wins = 0u8;
-                    let mut draws = 0u8;
-                    let mut o_wins = 0u8;
-
-                    for &(p, f) in split_range(pillz1) {
-                        if p == 0 {
-                            continue;
-                        }
-                        let mut g = game.clone();
-                        g.select(i, p, f);
-                        g.select(index, pillz, fury);
+                for &(p, f) in split_range(pillz2) {
+                    let mut g = game.clone();
+                    g.select(index, pillz, fury);
+                    g.select(i, p, f);
+                    search.count_battle();
 
-                        match g.status() {
-                            GameStatus::Player => p_wins += 1,
-                            GameStatus::Draw => draws += 1,
-                            GameStatus::Opponent => o_wins += 1,
-                            GameStatus::Playing => {
-                                let best = Solver::solve_first(&g);
-                                match best {
-                                    SelectionResult::Player(_) => p_wins += 1,
-                                    SelectionResult::Draw(_) => draws += 1,
-                                    SelectionResult::Opponent(_) => o_wins += 1,
-                                }
-                            }
-                        }
-                    }
-                    let (wins, losses) = if turn == PlayerType::Player {
-                        (p_wins, o_wins)
-                    } else {
-                        (o_wins, p_wins)
-                    };
-
-                    let rate = (wins + draws) as f32 / (wins + draws + losses) as f32;
-                    let rate_rounded = (rate * 100f32) as u32 / 10;
-                    if rate_rounded > best_rate_rounded
-                        || (rate_rounded == best_rate_rounded && pillz < best_pillz)
-                    {
-                        best_pillz = pillz;
-                        best_rate = rate;
-                        best_rate_rounded = rate_rounded;
-                        best_selection = Selection::new(index, pillz, fury);
+                    let (status, eval) = outcome(solver, &g, search);
+                    match status {
+                        GameStatus::Player => p_wins += 1,
+                        GameStatus::Draw => draws += 1,
+                        _ => o_wins += 1,
                     }
-
-                    Solver::print_count(pillz, fury, wins, draws, losses);
+                    total_eval += eval;
                 }
-                // println!();
-                println!("\n({:.1?}%) {}", best_rate * 100f32, best_selection);
-
-                (best_rate, best_selection, best_rate_rounded)
-            })
-            .max_by_key(|&(_, s, rate)| rate * 100 + (24 - s.pillz as u32))
-            .unwrap();
+            }
+            let (wins, losses) = if turn == PlayerType::Player {
+                (p_wins, o_wins)
+            } else {
+                (o_wins, p_wins)
+            };
+            let eval = if turn == PlayerType::Player {
+                total_eval
+            } else {
+                -total_eval
+            } / (wins + draws + losses).max(1) as f32;
+
+            let rate = (wins + draws) as f32 / (wins + draws + losses) as f32;
+            let rate_rounded = (rate * 100f32) as u32 / 10;
+            if rate_rounded > best_rate_rounded
+                || (rate_rounded == best_rate_rounded
+                    && (eval > best_eval || (eval == best_eval && pillz > best_pillz)))
+            {
+                best_pillz = pillz;
+                best_rate = rate;
+                best_rate_rounded = rate_rounded;
+                best_eval = eval;
+                best_selection = Selection::new(index, pillz, fury);
+            }
 
+            print_count(pillz, fury, wins, draws, losses);
+            outcomes.push(Outcome {
+                selection: Selection::new(index, pillz, fury),
+                wins,
+                draws,
+                losses,
+                eval,
+            });
+        }
+        // println!();
         println!(
-            "{}{}",
-            format!(" {:.1?}% ", best_rate * 100f32).black().on_green(),
-            format!(" {} ", best_selection).green()
+            "({:.1?}%) {}{}",
+            best_rate * 100f32,
+            best_selection,
+            eval_note(search, best_eval)
         );
     }
 
-    fn middle_first(game: &Game) {
-        let pillz1 = game.get_turn_player().pillz;
-        let pillz2 = game.get_turn_opponent().pillz;
-
-        let turn = game.get_turn();
-        let hand1 = game.get_turn_hand();
-        let hand2 = game.get_turn_opponent_hand();
-
-        let mut best_pillz = 0;
-        let mut best_rate = 0f32;
-        let mut best_rate_rounded = 0u32;
-        let mut best_selection = Selection::default();
+    println!(
+        "({:.1?}%) {}{}",
+        best_rate * 100f32,
+        best_selection,
+        eval_note(search, best_eval)
+    );
+    (best_rate, best_selection, outcomes)
+}
 
-        for index in 0..4 {
-            if hand1[index].played {
-                continue;
-            }
+fn middle_first_par<S: Solver + ?Sized>(
+    solver: &S,
+    game: &Game,
+    search: &Search,
+) -> (f32, Selection, Vec<Outcome>) {
+    let cards = (0..4)
+        // .filter(|&index| !game.get_turn_hand().index(index).played)
+        // .collect::<Vec<usize>>()
+        .into_par_iter()
+        .map(|index| {
+            // let hand1 = game.get_turn_hand();
+            let hand2 = game.get_turn_opponent_hand();
+            let pillz1 = game.get_turn_player().pillz;
+            let pillz2 = game.get_turn_opponent().pillz;
+            let turn = game.get_turn();
+
+            let mut best_pillz = 0;
+            let mut best_rate = 0f32;
+            let mut best_rate_rounded = 0u32;
+            let mut best_eval = f32::NEG_INFINITY;
+            let mut best_selection = Selection::default();
+            let mut outcomes = Vec::new();
 
             for &(pillz, fury) in shift_false_range(pillz1, game.round) {
                 let mut p_wins = 0;
                 let mut draws = 0;
                 let mut o_wins = 0;
+                let mut total_eval = 0f32;
 
                 for i in 0..4 {
                     if hand2[i].played {
@@ -306,23 +746,21 @@ impl Solver {
                     }
 
                     for &(p, f) in split_range(pillz2) {
+                        if p == 0 {
+                            continue;
+                        }
                         let mut g = game.clone();
                         g.select(index, pillz, fury);
                         g.select(i, p, f);
+                        search.count_battle();
 
-                        match g.status() {
+                        let (status, eval) = outcome(solver, &g, search);
+                        match status {
                             GameStatus::Player => p_wins += 1,
                             GameStatus::Draw => draws += 1,
-                            GameStatus::Opponent => o_wins += 1,
-                            GameStatus::Playing => {
-                                let best = Solver::solve_first(&g);
-                                match best {
-                                    SelectionResult::Player(_) => p_wins += 1,
-                                    SelectionResult::Draw(_) => draws += 1,
-                                    SelectionResult::Opponent(_) => o_wins += 1,
-                                }
-                            }
+                            _ => o_wins += 1,
                         }
+                        total_eval += eval;
                     }
                 }
                 let (wins, losses) = if turn == PlayerType::Player {
@@ -330,504 +768,324 @@ impl Solver {
                 } else {
                     (o_wins, p_wins)
                 };
+                let eval = if turn == PlayerType::Player {
+                    total_eval
+                } else {
+                    -total_eval
+                } / (wins + draws + losses).max(1) as f32;
 
                 let rate = (wins + draws) as f32 / (wins + draws + losses) as f32;
                 let rate_rounded = (rate * 100f32) as u32 / 10;
                 if rate_rounded > best_rate_rounded
-                    || (rate_rounded == best_rate_rounded && pillz > best_pillz)
+                    || (rate_rounded == best_rate_rounded
+                        && (eval > best_eval || (eval == best_eval && pillz > best_pillz)))
                 {
                     best_pillz = pillz;
                     best_rate = rate;
                     best_rate_rounded = rate_rounded;
+                    best_eval = eval;
                     best_selection = Selection::new(index, pillz, fury);
                 }
 
-                Solver::print_count(pillz, fury, wins, draws, losses);
+                print_count(pillz, fury, wins, draws, losses);
+                outcomes.push(Outcome {
+                    selection: Selection::new(index, pillz, fury),
+                    wins,
+                    draws,
+                    losses,
+                    eval,
+                });
             }
             // println!();
-            println!("({:.1?}%) {}", best_rate * 100f32, best_selection);
-        }
+            println!(
+                "\n({:.1?}%) {}{}",
+                best_rate * 100f32,
+                best_selection,
+                eval_note(search, best_eval)
+            );
+
+            (
+                best_rate,
+                best_selection,
+                best_rate_rounded,
+                best_eval,
+                outcomes,
+            )
+        })
+        .collect::<Vec<_>>();
+
+    let &(best_rate, best_selection, .., best_eval, _) = cards
+        .iter()
+        .max_by(|&&(_, a, a_rate, a_eval, _), &&(_, b, b_rate, b_eval, _)| {
+            a_rate
+                .cmp(&b_rate)
+                .then(a_eval.total_cmp(&b_eval))
+                .then(b.pillz.cmp(&a.pillz))
+        })
+        .unwrap();
 
-        println!("({:.1?}%) {}", best_rate * 100f32, best_selection);
-    }
-
-    fn middle_first_par(game: &Game) {
-        let (best_rate, best_selection, ..) = (0..4)
-            // .filter(|&index| !game.get_turn_hand().index(index).played)
-            // .collect::<Vec<usize>>()
-            .into_par_iter()
-            .map(|index| {
-                // let hand1 = game.get_turn_hand();
-                let hand2 = game.get_turn_opponent_hand();
-                let pillz1 = game.get_turn_player().pillz;
-                let pillz2 = game.get_turn_opponent().pillz;
-                let turn = game.get_turn();
-
-                let mut best_pillz = 0;
-                let mut best_rate = 0f32;
-                let mut best_rate_rounded = 0u32;
-                let mut best_selection = Selection::default();
-
-                for &(pillz, fury) in shift_false_range(pillz1, game.round) {
-                    let mut p_wins = 0;
-                    let mut draws = 0;
-                    let mut o_wins = 0;
-
-                    for i in 0..4 {
-                        if hand2[i].played {
-                            continue;
-                        }
-
-                        for &(p, f) in split_range(pillz2) {
-                            if p == 0 {
-                                continue;
-                            }
-                            let mut g = game.clone();
-                            g.select(index, pillz, fury);
-                            g.select(i, p, f);
-
-                            match g.status() {
-                                GameStatus::Player => p_wins += 1,
-                                GameStatus::Draw => draws += 1,
-                                GameStatus::Opponent => o_wins += 1,
-                                GameStatus::Playing => {
-                                    let best = Solver::solve_first(&g);
-                                    match best {
-                                        SelectionResult::Player(_) => p_wins += 1,
-                                        SelectionResult::Draw(_) => draws += 1,
-                                        SelectionResult::Opponent(_) => o_wins += 1,
-                                    }
-                                }
-                            }
-                        }
-                    }
-                    let (wins, losses) = if turn == PlayerType::Player {
-                        (p_wins, o_wins)
-                    } else {
-                        (o_wins, p_wins)
-                    };
-
-                    let rate = (wins + draws) as f32 / (wins + draws + losses) as f32;
-                    let rate_rounded = (rate * 100f32) as u32 / 10;
-                    if rate_rounded > best_rate_rounded
-                        || (rate_rounded == best_rate_rounded && pillz > best_pillz)
-                    {
-                        best_pillz = pillz;
-                        best_rate = rate;
-                        best_rate_rounded = rate_rounded;
-                        best_selection = Selection::new(index, pillz, fury);
-                    }
-
-                    Solver::print_count(pillz, fury, wins, draws, losses);
-                }
-                // println!();
-                println!("\n({:.1?}%) {}", best_rate * 100f32, best_selection);
-
-                (best_rate, best_selection, best_rate_rounded)
-            })
-            .max_by_key(|&(_, s, rate)| rate * 100 + (24 - s.pillz as u32))
-            .unwrap();
-
-        println!(
-            "{}{}",
-            format!(" {:.1?}% ", best_rate * 100f32).black().on_green(),
-            format!(" {} ", best_selection).green()
-        );
-    }
-
-    pub fn solve(game: &Game) -> SelectionResult {
-        let battle_count = unsafe { BATTLE_COUNT.load(Ordering::Relaxed) };
-        let now = Instant::now();
-
-        toggle_print();
-        let best = if game.s1.is_none() != game.s2.is_none() {
-            Solver::solve_second(&game)
-        } else {
-            Solver::solve_first(&game)
-        };
-        toggle_print();
-
-        let battles = unsafe { BATTLE_COUNT.load(Ordering::Relaxed) } - battle_count;
-        let elapsed = now.elapsed();
-        println!(
-            "{} {} /{:.1?}secs ({:.0?}k/s)",
-            " Battle Count ".white().on_bright_purple(),
-            battles,
-            elapsed.as_secs_f32(),
-            battles as f32 / elapsed.as_secs_f32() / 1000f32
-        );
-        // handler.join().unwrap();
-        best
-    }
-
-    pub fn solve_second(game: &Game) -> SelectionResult {
-        let turn = game.get_turn();
-        let i = if game.s1.is_none() {
-            game.s2.unwrap().index
-        } else {
-            game.s1.unwrap().index
-        };
+    println!(
+        "{}{}{}",
+        format!(" {:.1?}% ", best_rate * 100f32).black().on_green(),
+        format!(" {} ", best_selection).green(),
+        eval_note(search, best_eval)
+    );
+    let outcomes = cards.into_iter().flat_map(|card| card.4).collect();
+    (best_rate, best_selection, outcomes)
+}
 
-        let pillz1 = game.get_turn_player().pillz;
-        let pillz2 = game.get_turn_opponent().pillz;
+fn solve_second<S: Solver + ?Sized>(solver: &S, game: &Game, search: &Search) -> SelectionResult {
+    let turn = game.get_turn();
+    let i = if game.s1.is_none() {
+        game.s2.unwrap().index
+    } else {
+        game.s1.unwrap().index
+    };
 
-        let mut game = game.clone();
-        game.clear_selection();
+    let pillz1 = game.get_turn_player().pillz;
+    let pillz2 = game.get_turn_opponent().pillz;
 
-        let mut worst_result: Option<SelectionResult> = None;
+    let mut game = game.clone();
+    game.clear_selection();
 
-        for index in 0..4usize {
-            if game.get_turn_opponent_hand()[index].played {
-                continue;
-            }
+    let mut worst_result: Option<SelectionResult> = None;
 
-            for &(pillz, fury) in split_shift_range(pillz1) {
-                // for &(pillz, fury) in split_range(pillz1) {
-                let mut worst = GameResult::Win;
-                for &(p, f) in split_shift_range(pillz2) {
-                    // for &(p, f) in split_range(pillz2) {
-                    let mut g = game.clone();
-                    g.select(i, p, f);
-                    g.select(index, pillz, fury);
+    for index in 0..4usize {
+        if game.get_turn_opponent_hand()[index].played {
+            continue;
+        }
 
-                    match (g.status(), turn) {
-                        (GameStatus::Player, PlayerType::Opponent)
-                        | (GameStatus::Opponent, PlayerType::Player) => {
-                            worst = GameResult::Lose;
-                            break;
-                        }
-                        (GameStatus::Draw, _) => {
-                            worst = GameResult::Draw;
-                            continue;
-                        }
-                        (GameStatus::Player, _) | (GameStatus::Opponent, _) => continue,
-                        (GameStatus::Playing, _) => (),
+        for &(pillz, fury) in split_shift_range(pillz1) {
+            // for &(pillz, fury) in split_range(pillz1) {
+            let mut worst = GameResult::Win;
+            for &(p, f) in split_shift_range(pillz2) {
+                // for &(p, f) in split_range(pillz2) {
+                let mut g = game.clone();
+                g.select(i, p, f);
+                g.select(index, pillz, fury);
+                search.count_battle();
+
+                match (g.status(), turn) {
+                    (GameStatus::Player, PlayerType::Opponent)
+                    | (GameStatus::Opponent, PlayerType::Player) => {
+                        worst = GameResult::Lose;
+                        break;
                     }
-
-                    let best = Solver::solve_first(&g);
-
-                    match (best, turn) {
-                        (SelectionResult::Draw(_), _) => {
-                            worst = GameResult::Draw;
-                        }
-                        (SelectionResult::Opponent(_), PlayerType::Player)
-                        | (SelectionResult::Player(_), PlayerType::Opponent) => {
-                            worst = GameResult::Lose;
-                            break;
-                        }
-                        (_, _) => (),
+                    (GameStatus::Draw, _) => {
+                        worst = GameResult::Draw;
+                        continue;
                     }
+                    (GameStatus::Player, _) | (GameStatus::Opponent, _) => continue,
+                    (GameStatus::Playing, _) => (),
                 }
 
-                let selection = Selection { index, pillz, fury };
-                if worst == GameResult::Win {
-                    return if turn == PlayerType::Player {
-                        SelectionResult::Player(selection)
-                    } else {
-                        SelectionResult::Opponent(selection)
-                    };
-                } else if worst == GameResult::Draw {
-                    worst_result = Some(SelectionResult::Draw(selection));
-                } else if worst_result.is_none() {
-                    if turn == PlayerType::Player {
-                        worst_result = Some(SelectionResult::Opponent(selection))
-                    } else {
-                        worst_result = Some(SelectionResult::Player(selection))
+                let best = solver.best(&g, search);
+
+                match (best, turn) {
+                    (SelectionResult::Draw(_), _) => {
+                        worst = GameResult::Draw;
+                    }
+                    (SelectionResult::Opponent(_), PlayerType::Player)
+                    | (SelectionResult::Player(_), PlayerType::Opponent) => {
+                        worst = GameResult::Lose;
+                        break;
                     }
+                    (_, _) => (),
                 }
             }
-        }
 
-        worst_result.unwrap()
-    }
-
-    pub fn solve_first(game: &Game) -> SelectionResult {
-        let turn = game.get_turn();
-        let mut result: Option<SelectionResult> = None;
-
-        let pillz = game.get_turn_player().pillz;
-        for index in 0..4usize {
-            if game.get_turn_hand()[index].played {
-                continue;
-            }
-
-            for &(pillz, fury) in split_shift_range(pillz) {
-                // for &(pillz, fury) in split_range(pillz) {
-                let mut g = game.clone();
-
-                let battled = g.select(index, pillz, fury);
-
-                let status = g.status();
-                if battled && status != GameStatus::Playing {
-                    match (status, turn) {
-                        (GameStatus::Draw, _) => match result {
-                            None
-                            | Some(SelectionResult::Opponent(_))
-                            | Some(SelectionResult::Player(_)) => {
-                                result =
-                                    Some(SelectionResult::Draw(Selection { index, pillz, fury }));
-                            }
-                            _ => (),
-                        },
-                        (GameStatus::Opponent, PlayerType::Opponent) => {
-                            return SelectionResult::Opponent(Selection { index, pillz, fury });
-                        }
-                        (GameStatus::Player, PlayerType::Player) => {
-                            return SelectionResult::Player(Selection { index, pillz, fury });
-                        }
-                        (GameStatus::Opponent, PlayerType::Player) => {
-                            if result.is_none() {
-                                result = Some(SelectionResult::Opponent(Selection {
-                                    index,
-                                    pillz,
-                                    fury,
-                                }));
-                            }
-                        }
-                        (GameStatus::Player, PlayerType::Opponent) => {
-                            if result.is_none() {
-                                result =
-                                    Some(SelectionResult::Player(Selection { index, pillz, fury }));
-                            }
-                        }
-                        _ => (),
-                    }
+            let selection = Selection { index, pillz, fury };
+            if worst == GameResult::Win {
+                return if turn == PlayerType::Player {
+                    SelectionResult::Player(selection)
                 } else {
-                    let best = Solver::solve_first(&g);
-                    match (best, turn) {
-                        (SelectionResult::Draw(_), _) => {
-                            if result.is_none() {
-                                result =
-                                    Some(SelectionResult::Draw(Selection { index, pillz, fury }));
-                            }
-                        }
-                        (SelectionResult::Opponent(_), PlayerType::Opponent) => {
-                            return SelectionResult::Opponent(Selection { index, pillz, fury });
-                        }
-                        (SelectionResult::Player(_), PlayerType::Player) => {
-                            return SelectionResult::Player(Selection { index, pillz, fury });
-                        }
-                        (SelectionResult::Player(_), PlayerType::Opponent) => {
-                            if result.is_none() {
-                                result =
-                                    Some(SelectionResult::Player(Selection { index, pillz, fury }));
-                            }
-                        }
-                        (SelectionResult::Opponent(_), PlayerType::Player) => {
-                            if result.is_none() {
-                                result = Some(SelectionResult::Opponent(Selection {
-                                    index,
-                                    pillz,
-                                    fury,
-                                }));
-                            }
-                        }
-                    }
+                    SelectionResult::Opponent(selection)
+                };
+            } else if worst == GameResult::Draw {
+                worst_result = Some(SelectionResult::Draw(selection));
+            } else if worst_result.is_none() {
+                if turn == PlayerType::Player {
+                    worst_result = Some(SelectionResult::Opponent(selection))
+                } else {
+                    worst_result = Some(SelectionResult::Player(selection))
                 }
             }
         }
-
-        result.unwrap()
     }
-}
-
-static N: u8 = 32;
-lazy_static! {
-    #[derive(Debug)]
-    static ref SHIFT_RANGES: Vec<Vec<(u8, bool)>> = {
-        let mut ranges = Vec::with_capacity(N as usize);
-        for n in 0..N {
-            let mut range = Vec::new();
-
-            range.push((n, false));
-
-            if n < 3 {
-                for i in 0..n {
-                    range.push((i, false));
-                }
-            } else {
-                range.push((n - 3, true));
-                range.push((n - 3, false));
-
-                for i in 0..n - 3 {
-                    range.push((i, true));
-                    range.push((i, false));
-                }
 
-                range.push((n - 2, false));
-                range.push((n - 1, false));
-            }
-
-            ranges.push(range);
-        }
+    worst_result.unwrap()
+}
 
-        ranges
+#[cfg(test)]
+mod tests {
+    use super::{
+        split_shift_range, AlphaBeta, Engine, Evaluation, Minimax, Search, SelectionResult, Solver,
+        Tree,
     };
-    static ref SPLIT_SHIFT_RANGES: Vec<Vec<(u8, bool)>> = {
-        let mut ranges = Vec::with_capacity(N as usize);
-        for n in 0..N {
-            let mut range = Vec::new();
-
-            range.push((n, false));
-
-            if n < 3 {
-                for i in 0..n {
-                    range.push((i, false));
-                }
-            } else {
-                range.push((n - 3, false));
-
-                for i in 0..n - 3 {
-                    range.push((i, false));
-                }
-
-                range.push((n - 2, false));
-                range.push((n - 1, false));
-
-                range.push((n - 3, true));
-                for i in 0..n - 3 {
-                    range.push((i, true));
-                }
-            }
-
-            ranges.push(range);
-        }
-
-        ranges
+    use crate::{
+        card::Hand,
+        game::{Game, GameStatus, PlayerType, Selection},
+        output::Silent,
     };
-    static ref SHIFT_FALSE_RANGES: Vec<Vec<(u8, bool)>> = {
-        let mut ranges = Vec::with_capacity(N as usize);
-        for n in 0..N {
-            let mut range = Vec::new();
-
-            range.push((n, false));
 
-            if n < 3 {
-                for i in 0..n {
-                    range.push((i, false));
-                }
-            } else {
-                for i in 0..n - 2 {
-                    range.push((i, false));
-                }
-
-                range.push((n - 2, false));
-                range.push((n - 1, false));
-            }
+    #[test]
+    fn f() {
+        println!("Test -> {}", SelectionResult::Player(Selection::default()));
+        println!("Test -> {}", SelectionResult::Draw(Selection::default()));
+        println!(
+            "Test -> {}",
+            SelectionResult::Opponent(Selection::default())
+        );
+    }
 
-            ranges.push(range);
-        }
+    fn second_round() -> Game {
+        let h1 = Hand::from_names("Genmaicha", "Orka", "Sando", "Deborah");
+        let h2 = Hand::from_names("Nathan", "El Kuzco", "Noon Steevens", "Strygia");
+        let mut game = Game::new(h1, h2);
+        game.output = &Silent;
+        game.select(1, 3, false);
+        game.select(3, 0, false);
+        game
+    }
 
-        ranges
-    };
-    static ref RANGES: Vec<Vec<(u8, bool)>> = {
-        let mut ranges = Vec::with_capacity(N as usize);
-        for n in 0..N {
-            let mut range = Vec::new();
-
-            if n >= 3 {
-                for i in 0..n-2 {
-                    range.push((i, false));
-                    range.push((i, true));
-                }
+    fn third_round() -> Game {
+        let mut game = second_round();
+        game.select(0, 4, false);
+        game.select(0, 2, false);
+        game
+    }
 
-                for i in n-2..=n {
-                    range.push((i, false));
-                }
-            }
+    /// A game in its last round, small enough for every engine.
+    fn last_round() -> Game {
+        let mut game = third_round();
+        game.select(2, 1, false);
+        game.select(2, 2, false);
+        game
+    }
 
-            ranges.push(range);
+    fn verdict(result: SelectionResult) -> u8 {
+        match result {
+            SelectionResult::Player(_) => 0,
+            SelectionResult::Draw(_) => 1,
+            SelectionResult::Opponent(_) => 2,
         }
+    }
 
-        ranges
-    };
-    static ref SPLIT_RANGES: Vec<Vec<(u8, bool)>> = {
-        let mut ranges = Vec::with_capacity(N as usize);
-        for n in 0..N {
-            let mut range = Vec::new();
-
-            for i in 0..=n {
-                range.push((i, false));
-            }
+    #[test]
+    fn engines_agree() {
+        let mut game = last_round();
+        assert_eq!(game.round, 3);
+        let results = |game: &Game| {
+            [Engine::Minimax, Engine::Tree, Engine::AlphaBeta].map(|engine| {
+                let search = Search::default();
+                let result = engine.solver().solve(game, &search);
+                assert!(search.battles() > 0);
+                verdict(result)
+            })
+        };
 
-            if n >= 3 {
-                for i in 0..=n-3 {
-                    range.push((i, true));
-                }
-            }
+        let [minimax, tree, alpha_beta] = results(&game);
+        assert_eq!(minimax, tree);
+        assert_eq!(alpha_beta, tree);
 
-            ranges.push(range);
-        }
+        let turn = game.get_turn_hand();
+        let index = (0..4).find(|&i| !turn[i].played).unwrap();
+        game.select(index, 2, false);
+        let [minimax, tree, alpha_beta] = results(&game);
+        assert_eq!(minimax, tree);
+        assert_eq!(alpha_beta, tree);
+    }
 
-        ranges
-    };
-    static ref FALSE_RANGES: Vec<Vec<(u8, bool)>> = {
-        let mut ranges = Vec::with_capacity(N as usize);
-        for n in 0..N {
-            let mut range = Vec::new();
+    #[test]
+    fn table_finds_transpositions() {
+        let game = second_round();
+        let search = Search::default();
+        let result = Minimax.best(&game, &search);
+        assert!(search.table.hits() > 0);
+
+        // Solved positions are read back without another battle
+        let battles = search.battles();
+        assert_eq!(Minimax.best(&game, &search), result);
+        assert_eq!(search.battles(), battles);
+
+        let game = third_round();
+        let minimax = Minimax.best(&game, &Search::default());
+        let tree = Tree.best(&game, &Search::default());
+        assert_eq!(verdict(minimax), verdict(tree));
+    }
 
-            for i in 0..=n {
-                range.push((i, false));
+    /// Every line of play, scored by result then evaluation.
+    fn exhaustive(game: &Game, eval: &Evaluation) -> (u8, f32) {
+        let key = match game.status() {
+            GameStatus::Player => 2,
+            GameStatus::Draw => 1,
+            GameStatus::Opponent => 0,
+            GameStatus::Playing => {
+                let hand = game.get_turn_hand();
+                let scores = (0..4).filter(|&i| !hand[i].played).flat_map(|index| {
+                    split_shift_range(game.get_turn_player().pillz).map(move |&(pillz, fury)| {
+                        let mut g = *game;
+                        g.select(index, pillz, fury);
+                        exhaustive(&g, eval)
+                    })
+                });
+                let cmp = |a: &(u8, f32), b: &(u8, f32)| a.0.cmp(&b.0).then(a.1.total_cmp(&b.1));
+                return match game.get_turn() {
+                    PlayerType::Player => scores.max_by(cmp),
+                    PlayerType::Opponent => scores.min_by(cmp),
+                }
+                .unwrap();
             }
+        };
+        (key, eval.score(game))
+    }
 
-            ranges.push(range);
-        }
-
-        ranges
-    };
-}
-
-// #[inline]
-// fn shift_range(n: u8) -> Iter<'static, (u8, bool)> {
-//     SHIFT_RANGES[n as usize].iter()
-// }
-
-#[inline]
-fn split_shift_range(n: u8) -> Iter<'static, (u8, bool)> {
-    SPLIT_SHIFT_RANGES[n as usize].iter()
-}
+    #[test]
+    fn evaluation_breaks_ties() {
+        let game = third_round();
+        assert_eq!(game.status(), GameStatus::Playing);
+        let plain = AlphaBeta.best(&game, &Search::default());
 
-// #[inline]
-// fn range(n: u8) -> Iter<'static, (u8, bool)> {
-//     RANGES[n as usize].iter()
-// }
+        let eval: Evaluation = "life,ko=5".parse().unwrap();
+        let search = Search::new(eval);
+        let (result, value) = AlphaBeta.value(&game, &search);
+        assert_eq!(verdict(result), verdict(plain));
 
-#[inline]
-fn split_range(n: u8) -> Iter<'static, (u8, bool)> {
-    SPLIT_RANGES[n as usize].iter()
-}
+        let (key, best) = exhaustive(&game, &eval);
+        assert_eq!(2 - key, verdict(result));
+        assert_eq!(value, best);
 
-// #[inline]
-// fn false_range(n: u8, round: u8) -> Iter<'static, (u8, bool)> {
-//     if round == 0 {
-//         FALSE_RANGES[n as usize].iter()
-//     } else {
-//         SPLIT_RANGES[n as usize].iter()
-//     }
-// }
-
-#[inline]
-fn shift_false_range(n: u8, round: u8) -> Iter<'static, (u8, bool)> {
-    if round == 0 {
-        SHIFT_FALSE_RANGES[n as usize].iter()
-    } else {
-        SPLIT_SHIFT_RANGES[n as usize].iter()
+        // Every engine finds the same evaluation
+        assert_eq!(Minimax.value(&game, &search).1, best);
+        assert_eq!(Tree.value(&game, &search).1, best);
     }
-}
 
-#[test]
-fn test() {
-    for i in 0..N {
-        println!("{:?}", SPLIT_RANGES[i as usize]);
+    #[test]
+    fn mixed_strategy_beats_the_worst_case() {
+        let h1 = Hand::from_names("Vivian", "Sylvia Ld", "Lola Noel", "Elvis");
+        let h2 = Hand::from_names("Danae", "Dr Falkenstein", "Demonink", "Endeavour");
+        let mut game = Game::new(h1, h2);
+        game.output = &Silent;
+        game.select(2, 6, false);
+        game.select(2, 10, false);
+
+        // Seeing the opponent's pillz, the player would always win
+        let search = Search::default();
+        let result = AlphaBeta.solve(&game, &search);
+        assert!(matches!(result, SelectionResult::Player(_)));
+
+        // It can't, so the opponent mixes them and does better than a loss
+        let strategy = AlphaBeta.mixed(&game, &search);
+        assert!(strategy.value > -1.0);
+        assert!(strategy.choices.len() > 1);
+        let total: f32 = strategy.choices.iter().map(|c| c.probability).sum();
+        assert!((total - 1.0).abs() < 1e-4);
+
+        // Once the card is shown, the player's side of the same game
+        game.select(strategy.choices[0].selection.index, 0, false);
+        let reply = AlphaBeta.mixed(&game, &search);
+        assert!((reply.value + strategy.value).abs() < 1e-4);
+        // Its later positions were solved by the first call
+        assert!(search.values.hits() > 0);
     }
 }
-
-#[test]
-fn f() {
-    println!("Test -> {}", SelectionResult::Player(Selection::default()));
-    println!("Test -> {}", SelectionResult::Draw(Selection::default()));
-    println!(
-        "Test -> {}",
-        SelectionResult::Opponent(Selection::default())
-    );
-}
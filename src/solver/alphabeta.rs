@@ -0,0 +1,222 @@
+use std::cmp::Reverse;
+
+use super::{eval::Score, split_shift_range, table::Bound, Search, SelectionResult, Solver};
+use crate::game::{Game, GameStatus, PlayerType, Selection};
+
+/// Alpha-beta search over the selections of both players.
+///
+/// Unlike `Minimax`, a branch is dropped as soon as it can't beat what the
+/// other player already has elsewhere, not only when it's a forced win, and
+/// a draw is always preferred to a loss, so it finds the results of `Tree`.
+/// Selections that cut a branch before are tried first.
+///
+/// With an evaluation, results are compared first, then evaluations.
+pub struct AlphaBeta;
+
+impl Solver for AlphaBeta {
+    fn value(&self, game: &Game, search: &Search) -> (SelectionResult, f32) {
+        let (lowest, highest) = Score::bounds(search);
+        let (score, selection) =
+            AlphaBeta::search(game, search, &mut Order::default(), lowest, highest);
+        (score.result(selection), score.eval)
+    }
+
+    fn solve(&self, game: &Game, search: &Search) -> SelectionResult {
+        let Some(pending) = (match (game.s1, game.s2) {
+            (Some(s), None) | (None, Some(s)) => Some(s),
+            _ => None,
+        }) else {
+            return self.best(game, search);
+        };
+
+        // The pending selection only shows its card, so assume the pillz
+        // and fury that are worst for the player to move
+        let maximizing = game.get_turn() == PlayerType::Player;
+        let hand = *game.get_turn_hand();
+        let pillz = game.get_turn_player().pillz;
+        let pillz_opp = game.get_turn_opponent().pillz;
+        let mut base = *game;
+        base.clear_selection();
+
+        let (lowest, highest) = Score::bounds(search);
+        let top = if maximizing { highest } else { lowest };
+        let mut order = Order::default();
+        let mut best: Option<(Score, Selection)> = None;
+        for index in (0..4).filter(|&i| !hand[i].played) {
+            for &(p, f) in split_shift_range(pillz) {
+                let selection = Selection::new(index, p, f);
+                // Replies only matter while they can do worse than `best`
+                let (mut alpha, mut beta) = match best {
+                    Some((score, _)) if maximizing => (score, highest),
+                    Some((score, _)) => (lowest, score),
+                    None => (lowest, highest),
+                };
+
+                let mut worst = top;
+                for &(p, f) in split_shift_range(pillz_opp) {
+                    let mut g = base;
+                    g.select(pending.index, p, f);
+                    g.select(index, selection.pillz, selection.fury);
+                    search.count_battle();
+
+                    let score = match g.status() {
+                        GameStatus::Playing => {
+                            AlphaBeta::search(&g, search, &mut order, alpha, beta).0
+                        }
+                        _ => Score::terminal(&g, search),
+                    };
+                    if maximizing {
+                        worst = worst.min(score);
+                        beta = beta.min(score);
+                    } else {
+                        worst = worst.max(score);
+                        alpha = alpha.max(score);
+                    }
+                    if alpha >= beta {
+                        break;
+                    }
+                }
+
+                let better = match best {
+                    None => true,
+                    Some((score, _)) if maximizing => worst > score,
+                    Some((score, _)) => worst < score,
+                };
+                if better {
+                    best = Some((worst, selection));
+                }
+                if worst == top {
+                    return worst.result(selection);
+                }
+            }
+        }
+
+        let (score, selection) = best.unwrap();
+        score.result(selection)
+    }
+}
+
+impl AlphaBeta {
+    /// Score of the position within `alpha..=beta` and the selection that
+    /// gets it. A score at or past a bound only means the real one is at
+    /// least as far.
+    fn search(
+        game: &Game,
+        search: &Search,
+        order: &mut Order,
+        mut alpha: Score,
+        mut beta: Score,
+    ) -> (Score, Selection) {
+        let key = game.position_hash();
+        let mut stored = None;
+        if let Some((result, eval, bound)) = search.table.probe(key) {
+            let (score, selection) = (Score::of(result, eval), *result.selection());
+            match bound {
+                Bound::Exact => return (score, selection),
+                Bound::Lower => alpha = alpha.max(score),
+                Bound::Upper => beta = beta.min(score),
+            }
+            if alpha >= beta {
+                return (score, selection);
+            }
+            stored = Some(selection);
+        }
+
+        let maximizing = game.get_turn() == PlayerType::Player;
+        let (alpha_in, beta_in) = (alpha, beta);
+        let ply = Order::ply(game);
+
+        let mut best: Option<(Score, Selection)> = None;
+        for selection in order.moves(game, ply, stored) {
+            let mut g = *game;
+            let battled = g
+                .select(selection.index, selection.pillz, selection.fury)
+                .is_some();
+            if battled {
+                search.count_battle();
+            }
+
+            let score = if battled && g.status() != GameStatus::Playing {
+                Score::terminal(&g, search)
+            } else {
+                AlphaBeta::search(&g, search, order, alpha, beta).0
+            };
+
+            let better = match best {
+                None => true,
+                Some((best, _)) if maximizing => score > best,
+                Some((best, _)) => score < best,
+            };
+            if better {
+                best = Some((score, selection));
+            }
+            if maximizing {
+                alpha = alpha.max(score);
+            } else {
+                beta = beta.min(score);
+            }
+            if alpha >= beta {
+                order.cutoff(ply, selection);
+                break;
+            }
+        }
+
+        let (score, selection) = best.unwrap();
+        // A score cut off by a bound is only a bound, unless none is past it
+        let (lowest, highest) = Score::bounds(search);
+        let bound = if score <= alpha_in && score != lowest {
+            Bound::Upper
+        } else if score >= beta_in && score != highest {
+            Bound::Lower
+        } else {
+            Bound::Exact
+        };
+        search
+            .table
+            .store(key, score.result(selection), score.eval, bound);
+        (score, selection)
+    }
+}
+/// Move ordering learned during one search.
+#[derive(Default)]
+struct Order {
+    /// The last selection that cut off a branch, per ply.
+    killers: [Option<Selection>; 8],
+    /// How often each pillz and fury choice cut off a branch.
+    history: [[u32; 2]; 32],
+}
+
+impl Order {
+    /// Two plies per round, one for each selection.
+    fn ply(game: &Game) -> usize {
+        (game.round as usize * 2 + game.has_someone_selected() as usize).min(7)
+    }
+
+    /// The selections of the player to move: the best one of an earlier
+    /// search of the position, the killer, then the pillz and fury that cut
+    /// off the most branches.
+    fn moves(&self, game: &Game, ply: usize, stored: Option<Selection>) -> Vec<Selection> {
+        let hand = game.get_turn_hand();
+        let pillz = game.get_turn_player().pillz;
+        let mut moves: Vec<Selection> = (0..4)
+            .filter(|&i| !hand[i].played)
+            .flat_map(|index| {
+                split_shift_range(pillz)
+                    .map(move |&(pillz, fury)| Selection::new(index, pillz, fury))
+            })
+            .collect();
+        moves.sort_by_key(|&s| {
+            (
+                Some(s) != stored,
+                Some(s) != self.killers[ply],
+                Reverse(self.history[s.pillz as usize][s.fury as usize]),
+            )
+        });
+        moves
+    }
+
+    fn cutoff(&mut self, ply: usize, selection: Selection) {
+        self.killers[ply] = Some(selection);
+        self.history[selection.pillz as usize][selection.fury as usize] += 1;
+    }
+}
@@ -0,0 +1,229 @@
+use std::{
+    cmp::Ordering,
+    fmt::{Display, Formatter},
+    str::FromStr,
+};
+
+use serde::{Deserialize, Serialize};
+
+use super::{Search, SelectionResult};
+use crate::game::{Game, GameStatus, Selection};
+
+/// How finished games with the same result compare, for the solvers to pick
+/// the better of two wins or the lesser of two losses.
+///
+/// The score is a weighted sum from the player's side, so the opponent's
+/// lead counts against it. All weights zero only keeps the result.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Evaluation {
+    /// Per point of life over the opponent's.
+    pub life: f32,
+    /// Per pillz left over the opponent's.
+    pub pillz: f32,
+    /// For a game won by knocking the opponent out, rather than on life
+    /// after the last round.
+    pub ko: f32,
+}
+
+impl Evaluation {
+    /// Whether only the result counts, which the solvers can search faster.
+    pub fn is_none(&self) -> bool {
+        *self == Evaluation::default()
+    }
+
+    /// Score of a finished game for the player.
+    pub fn score(&self, game: &Game) -> f32 {
+        if self.is_none() {
+            return 0.0;
+        }
+        let (p1, p2) = (&game.p1, &game.p2);
+        let ko = match (p1.life, p2.life) {
+            (0, 0) => 0.0,
+            (_, 0) => 1.0,
+            (0, _) => -1.0,
+            _ => 0.0,
+        };
+        self.life * (p1.life as f32 - p2.life as f32)
+            + self.pillz * (p1.pillz as f32 - p2.pillz as f32)
+            + self.ko * ko
+    }
+}
+
+/// Parses `result`, a single term like `life`, or terms with weights like
+/// `life=1,pillz=0.5,ko=4`. A term without a weight weighs 1.
+impl FromStr for Evaluation {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut evaluation = Evaluation::default();
+        if s == "result" {
+            return Ok(evaluation);
+        }
+
+        for term in s.split(',') {
+            let (name, weight) = match term.split_once('=') {
+                Some((name, weight)) => {
+                    let weight = weight
+                        .trim()
+                        .parse()
+                        .map_err(|_| format!("Invalid weight {:?} in {:?}", weight, term))?;
+                    (name.trim(), weight)
+                }
+                None => (term.trim(), 1.0),
+            };
+            match name {
+                "life" => evaluation.life = weight,
+                "pillz" => evaluation.pillz = weight,
+                "ko" => evaluation.ko = weight,
+                _ => {
+                    return Err(format!(
+                        "Unknown evaluation term {:?}, expected life, pillz or ko",
+                        name
+                    ))
+                }
+            }
+        }
+        Ok(evaluation)
+    }
+}
+
+impl Display for Evaluation {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        if self.is_none() {
+            return write!(f, "result");
+        }
+        let terms: Vec<String> = [("life", self.life), ("pillz", self.pillz), ("ko", self.ko)]
+            .into_iter()
+            .filter(|&(_, weight)| weight != 0.0)
+            .map(|(name, weight)| format!("{}={}", name, weight))
+            .collect();
+        write!(f, "{}", terms.join(","))
+    }
+}
+
+/// Results are from the player's side: the opponent wins, draw, the player
+/// wins.
+const LOSE: i8 = -1;
+const DRAW: i8 = 0;
+const WIN: i8 = 1;
+
+/// The result of a position, then the evaluation of the game it leads to,
+/// both from the player's side. Results are compared first, then
+/// evaluations.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Score {
+    result: i8,
+    pub eval: f32,
+}
+
+impl Score {
+    /// The lowest and highest scores. Without an evaluation they are plain
+    /// results, so a win still ends the search of a position.
+    pub(crate) fn bounds(search: &Search) -> (Score, Score) {
+        let (low, high) = if search.eval.is_none() {
+            (0.0, 0.0)
+        } else {
+            (f32::NEG_INFINITY, f32::INFINITY)
+        };
+        (
+            Score {
+                result: LOSE,
+                eval: low,
+            },
+            Score {
+                result: WIN,
+                eval: high,
+            },
+        )
+    }
+
+    pub(crate) fn terminal(game: &Game, search: &Search) -> Score {
+        let result = match game.status() {
+            GameStatus::Player => WIN,
+            GameStatus::Opponent => LOSE,
+            GameStatus::Draw | GameStatus::Playing => DRAW,
+        };
+        Score {
+            result,
+            eval: search.eval.score(game),
+        }
+    }
+
+    pub(crate) fn of(result: SelectionResult, eval: f32) -> Score {
+        let result = match result {
+            SelectionResult::Player(_) => WIN,
+            SelectionResult::Draw(_) => DRAW,
+            SelectionResult::Opponent(_) => LOSE,
+        };
+        Score { result, eval }
+    }
+
+    pub(crate) fn result(self, selection: Selection) -> SelectionResult {
+        match self.result {
+            WIN => SelectionResult::Player(selection),
+            LOSE => SelectionResult::Opponent(selection),
+            _ => SelectionResult::Draw(selection),
+        }
+    }
+}
+
+impl Ord for Score {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.result
+            .cmp(&other.result)
+            .then(self.eval.total_cmp(&other.eval))
+    }
+}
+
+impl PartialOrd for Score {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for Score {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Score {}
+
+#[cfg(test)]
+mod tests {
+    use super::Evaluation;
+    use crate::{card::Hand, game::Game};
+
+    #[test]
+    fn parses_weights() {
+        assert!("result".parse::<Evaluation>().unwrap().is_none());
+        let evaluation: Evaluation = "life,ko=4".parse().unwrap();
+        assert_eq!(
+            evaluation,
+            Evaluation {
+                life: 1.0,
+                pillz: 0.0,
+                ko: 4.0
+            }
+        );
+        assert_eq!(evaluation.to_string().parse::<Evaluation>(), Ok(evaluation));
+        assert!("life=a".parse::<Evaluation>().is_err());
+        assert!("hp".parse::<Evaluation>().is_err());
+    }
+
+    #[test]
+    fn scores_from_the_players_side() {
+        let h1 = Hand::from_names("Figaro", "Elvis", "Danae", "Vivian");
+        let h2 = Hand::from_names("Cell", "John O Clock", "Hollow Spyke", "Dr Falkenstein");
+        let mut game = Game::new(h1, h2);
+        game.p1.life = 5;
+        game.p2.life = 0;
+        game.p1.pillz = 2;
+        game.p2.pillz = 6;
+
+        let evaluation: Evaluation = "life=1,pillz=0.5,ko=10".parse().unwrap();
+        assert_eq!(evaluation.score(&game), 5.0 - 2.0 + 10.0);
+        assert_eq!(Evaluation::default().score(&game), 0.0);
+    }
+}
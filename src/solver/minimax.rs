@@ -0,0 +1,154 @@
+use super::{eval::Score, split_shift_range, table::Bound, Search, SelectionResult, Solver};
+use crate::game::{Game, GameStatus, PlayerType, Selection};
+
+/// Depth-first search over the selections of the player to move, returning
+/// as soon as one forces a win.
+///
+/// With an evaluation, a win doesn't end the search, since another
+/// selection may win by more.
+pub struct Minimax;
+
+impl Solver for Minimax {
+    fn value(&self, game: &Game, search: &Search) -> (SelectionResult, f32) {
+        if search.eval.is_none() {
+            return (search.cached(game, || Minimax::search(game, search)), 0.0);
+        }
+
+        let key = game.position_hash();
+        if let Some((result, eval, Bound::Exact)) = search.table.probe(key) {
+            return (result, eval);
+        }
+        let (score, selection) = Minimax::search_eval(game, search);
+        let result = score.result(selection);
+        search.table.store(key, result, score.eval, Bound::Exact);
+        (result, score.eval)
+    }
+}
+
+impl Minimax {
+    /// Every selection of the player to move, for the best result and then
+    /// the best evaluation.
+    fn search_eval(game: &Game, search: &Search) -> (Score, Selection) {
+        let maximizing = game.get_turn() == PlayerType::Player;
+        let hand = game.get_turn_hand();
+        let mut best: Option<(Score, Selection)> = None;
+
+        for index in (0..4).filter(|&i| !hand[i].played) {
+            for &(pillz, fury) in split_shift_range(game.get_turn_player().pillz) {
+                let mut g = *game;
+                let battled = g.select(index, pillz, fury).is_some();
+                if battled {
+                    search.count_battle();
+                }
+
+                let score = if battled && g.status() != GameStatus::Playing {
+                    Score::terminal(&g, search)
+                } else {
+                    let (result, eval) = Minimax.value(&g, search);
+                    Score::of(result, eval)
+                };
+                let better = match best {
+                    None => true,
+                    Some((best, _)) if maximizing => score > best,
+                    Some((best, _)) => score < best,
+                };
+                if better {
+                    best = Some((score, Selection::new(index, pillz, fury)));
+                }
+            }
+        }
+        best.unwrap()
+    }
+
+    fn search(game: &Game, search: &Search) -> SelectionResult {
+        let turn = game.get_turn();
+        let mut result: Option<SelectionResult> = None;
+
+        let pillz = game.get_turn_player().pillz;
+        for index in 0..4usize {
+            if game.get_turn_hand()[index].played {
+                continue;
+            }
+
+            for &(pillz, fury) in split_shift_range(pillz) {
+                // for &(pillz, fury) in split_range(pillz) {
+                let mut g = game.clone();
+
+                let battled = g.select(index, pillz, fury).is_some();
+                if battled {
+                    search.count_battle();
+                }
+
+                let status = g.status();
+                if battled && status != GameStatus::Playing {
+                    match (status, turn) {
+                        (GameStatus::Draw, _) => match result {
+                            None
+                            | Some(SelectionResult::Opponent(_))
+                            | Some(SelectionResult::Player(_)) => {
+                                result =
+                                    Some(SelectionResult::Draw(Selection { index, pillz, fury }));
+                            }
+                            _ => (),
+                        },
+                        (GameStatus::Opponent, PlayerType::Opponent) => {
+                            return SelectionResult::Opponent(Selection { index, pillz, fury });
+                        }
+                        (GameStatus::Player, PlayerType::Player) => {
+                            return SelectionResult::Player(Selection { index, pillz, fury });
+                        }
+                        (GameStatus::Opponent, PlayerType::Player) => {
+                            if result.is_none() {
+                                result = Some(SelectionResult::Opponent(Selection {
+                                    index,
+                                    pillz,
+                                    fury,
+                                }));
+                            }
+                        }
+                        (GameStatus::Player, PlayerType::Opponent) => {
+                            if result.is_none() {
+                                result =
+                                    Some(SelectionResult::Player(Selection { index, pillz, fury }));
+                            }
+                        }
+                        _ => (),
+                    }
+                } else {
+                    let best = Minimax.best(&g, search);
+                    match (best, turn) {
+                        (SelectionResult::Draw(_), _) => {
+                            if result.is_none() {
+                                result =
+                                    Some(SelectionResult::Draw(Selection { index, pillz, fury }));
+                            }
+                        }
+                        (SelectionResult::Opponent(_), PlayerType::Opponent) => {
+                            return SelectionResult::Opponent(Selection { index, pillz, fury });
+                        }
+                        (SelectionResult::Player(_), PlayerType::Player) => {
+                            return SelectionResult::Player(Selection { index, pillz, fury });
+                        }
+                        (SelectionResult::Player(_), PlayerType::Opponent) => {
+                            if result.is_none() {
+                                result =
+                                    Some(SelectionResult::Player(Selection { index, pillz, fury }));
+                            }
+                        }
+                        (SelectionResult::Opponent(_), PlayerType::Player) => {
+                            if result.is_none() {
+                                result = Some(SelectionResult::Opponent(Selection {
+                                    index,
+                                    pillz,
+                                    fury,
+                                }));
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
+        result.unwrap()
+    }
+}
@@ -0,0 +1,304 @@
+use rayon::prelude::{IntoParallelIterator, ParallelIterator};
+
+use super::{split_shift_range, Choice, Search, Strategy};
+use crate::{
+    card::Hand,
+    game::{Game, GameStatus, PlayerType, Selection},
+};
+
+/// Below this, a tableau entry counts as zero.
+const EPSILON: f64 = 1e-9;
+
+/// Optimal mixed strategies of a zero-sum matrix game.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Equilibrium {
+    /// What the row player gets on average when both play their strategy.
+    pub value: f64,
+    /// Probability of each row, for the row player, who maximizes.
+    pub rows: Vec<f64>,
+    /// Probability of each column, for the column player, who minimizes.
+    pub cols: Vec<f64>,
+}
+
+impl Equilibrium {
+    /// Solves the game with the simplex method.
+    ///
+    /// The payoffs are shifted to be positive, then the column player's
+    /// program is maximizing `sum(y)` with `payoff * y <= 1` and `y >= 0`.
+    /// Its optimum is one over the value, and the row player's strategy is
+    /// read from the dual prices of the constraints.
+    pub fn solve(payoff: &[Vec<f64>]) -> Self {
+        let (m, n) = (payoff.len(), payoff[0].len());
+        let min = payoff
+            .iter()
+            .flatten()
+            .copied()
+            .fold(f64::INFINITY, f64::min);
+        let shift = 1.0 - min;
+
+        // Constraint rows then the objective row; columns are y, the slacks,
+        // then the right-hand side.
+        let width = n + m + 1;
+        let mut tableau = vec![vec![0.0; width]; m + 1];
+        for (i, row) in payoff.iter().enumerate() {
+            for (j, &value) in row.iter().enumerate() {
+                tableau[i][j] = value + shift;
+            }
+            tableau[i][n + i] = 1.0;
+            tableau[i][width - 1] = 1.0;
+        }
+        tableau[m][..n].fill(-1.0);
+        let mut basis: Vec<usize> = (n..n + m).collect();
+
+        // Bland's rule: the first improving column and the lowest basic
+        // variable on ties, so degenerate pivots can't cycle
+        while let Some(col) = (0..width - 1).find(|&j| tableau[m][j] < -EPSILON) {
+            let row = (0..m)
+                .filter(|&i| tableau[i][col] > EPSILON)
+                .min_by(|&a, &b| {
+                    let ratio = |i: usize| tableau[i][width - 1] / tableau[i][col];
+                    ratio(a)
+                        .partial_cmp(&ratio(b))
+                        .unwrap()
+                        .then(basis[a].cmp(&basis[b]))
+                })
+                .expect("matrix games are bounded");
+
+            let pivot = tableau[row][col];
+            tableau[row].iter_mut().for_each(|v| *v /= pivot);
+            let pivot_row = tableau[row].clone();
+            for (i, line) in tableau.iter_mut().enumerate() {
+                let factor = line[col];
+                if i != row && factor != 0.0 {
+                    for (v, p) in line.iter_mut().zip(&pivot_row) {
+                        *v -= factor * p;
+                    }
+                }
+            }
+            basis[row] = col;
+        }
+
+        let total = tableau[m][width - 1];
+        let mut cols = vec![0.0; n];
+        for (i, &var) in basis.iter().enumerate() {
+            if var < n {
+                cols[var] = tableau[i][width - 1] / total;
+            }
+        }
+        let rows = (0..m).map(|i| tableau[m][n + i] / total).collect();
+
+        Equilibrium {
+            value: 1.0 / total - shift,
+            rows,
+            cols,
+        }
+    }
+}
+
+/// Every selection of `cards` from the hand, with every pillz and fury.
+fn selections(hand: &Hand, pillz: u8, cards: impl Iterator<Item = usize>) -> Vec<Selection> {
+    cards
+        .filter(|&index| !hand[index].played)
+        .flat_map(|index| {
+            split_shift_range(pillz).map(move |&(pillz, fury)| Selection::new(index, pillz, fury))
+        })
+        .collect()
+}
+
+/// Value of a position between rounds for the player: 1 for a win, 0 for a
+/// draw and -1 for a loss, with both players mixing over the hidden pillz
+/// and fury in every round left.
+fn value(game: &Game, search: &Search) -> f64 {
+    match game.status() {
+        GameStatus::Player => return 1.0,
+        GameStatus::Opponent => return -1.0,
+        GameStatus::Draw => return 0.0,
+        GameStatus::Playing => (),
+    }
+    let key = game.position_hash();
+    if let Some(value) = search.values.probe(key) {
+        return value;
+    }
+
+    let turn = game.get_turn();
+    let hand = game.get_turn_hand();
+    let pillz = game.get_turn_player().pillz;
+    let replies = selections(
+        game.get_turn_opponent_hand(),
+        game.get_turn_opponent().pillz,
+        0..4,
+    );
+    let best = (0..4)
+        .filter(|&index| !hand[index].played)
+        .map(|index| {
+            let rows = selections(hand, pillz, index..index + 1);
+            Equilibrium::solve(&payoffs(game, &rows, &replies, turn, search)).value
+        })
+        .fold(f64::NEG_INFINITY, f64::max);
+
+    let value = match turn {
+        PlayerType::Player => best,
+        PlayerType::Opponent => -best,
+    };
+    search.values.put(key, value);
+    value
+}
+
+/// The value of both selections for `turn`, with the later rounds played
+/// as matrix games too.
+fn payoff(
+    game: &Game,
+    first: Selection,
+    second: Selection,
+    turn: PlayerType,
+    search: &Search,
+) -> f64 {
+    let mut g = *game;
+    g.select(first.index, first.pillz, first.fury);
+    g.select(second.index, second.pillz, second.fury);
+    search.count_battle();
+
+    let value = value(&g, search);
+    match turn {
+        PlayerType::Player => value,
+        PlayerType::Opponent => -value,
+    }
+}
+
+/// The payoff of every selection in `rows` against every reply.
+fn payoffs(
+    game: &Game,
+    rows: &[Selection],
+    replies: &[Selection],
+    turn: PlayerType,
+    search: &Search,
+) -> Vec<Vec<f64>> {
+    rows.iter()
+        .map(|&row| {
+            replies
+                .iter()
+                .map(|&reply| payoff(game, row, reply, turn, search))
+                .collect()
+        })
+        .collect()
+}
+
+fn strategy(selections: Vec<Selection>, equilibrium: Equilibrium) -> Strategy {
+    let choices = selections
+        .into_iter()
+        .zip(equilibrium.rows)
+        .filter(|&(_, probability)| probability > EPSILON)
+        .map(|(selection, probability)| Choice {
+            selection,
+            probability: probability as f32,
+        })
+        .collect();
+    Strategy {
+        value: equilibrium.value as f32,
+        choices,
+    }
+}
+
+/// The player to move picks first. Its card is then shown, so each card is
+/// its own game against every reply, and the card with the best value is
+/// played.
+pub(crate) fn first(game: &Game, search: &Search) -> Strategy {
+    let turn = game.get_turn();
+    let hand = game.get_turn_hand();
+    let pillz = game.get_turn_player().pillz;
+    let replies = selections(
+        game.get_turn_opponent_hand(),
+        game.get_turn_opponent().pillz,
+        0..4,
+    );
+
+    (0..4)
+        .filter(|&index| !hand[index].played)
+        .collect::<Vec<usize>>()
+        .into_par_iter()
+        .map(|index| {
+            let rows = selections(hand, pillz, index..index + 1);
+            let payoffs = payoffs(game, &rows, &replies, turn, search);
+            strategy(rows, Equilibrium::solve(&payoffs))
+        })
+        .collect::<Vec<Strategy>>()
+        .into_iter()
+        // The first card on ties, whichever thread finished first
+        .reduce(|a, b| {
+            if b.value > a.value + EPSILON as f32 {
+                b
+            } else {
+                a
+            }
+        })
+        .unwrap()
+}
+
+/// The player to move picks second: it sees the card of the pending
+/// selection, but not its pillz and fury.
+pub(crate) fn second(game: &Game, search: &Search) -> Strategy {
+    let turn = game.get_turn();
+    let pending = match (game.s1, game.s2) {
+        (Some(s), None) | (None, Some(s)) => s,
+        _ => unreachable!("no pending selection"),
+    };
+    let rows = selections(game.get_turn_hand(), game.get_turn_player().pillz, 0..4);
+
+    let mut base = *game;
+    base.clear_selection();
+    let hidden: Vec<Selection> = split_shift_range(game.get_turn_opponent().pillz)
+        .map(|&(pillz, fury)| Selection::new(pending.index, pillz, fury))
+        .collect();
+
+    let payoffs: Vec<Vec<f64>> = rows
+        .clone()
+        .into_par_iter()
+        .map(|row| {
+            hidden
+                .iter()
+                .map(|&first| payoff(&base, first, row, turn, search))
+                .collect()
+        })
+        .collect();
+    strategy(rows, Equilibrium::solve(&payoffs))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Equilibrium;
+
+    fn close(a: f64, b: f64) -> bool {
+        (a - b).abs() < 1e-6
+    }
+
+    #[test]
+    fn matching_pennies() {
+        let equilibrium = Equilibrium::solve(&[vec![1.0, -1.0], vec![-1.0, 1.0]]);
+        assert!(close(equilibrium.value, 0.0));
+        assert!(equilibrium.rows.iter().all(|&p| close(p, 0.5)));
+        assert!(equilibrium.cols.iter().all(|&p| close(p, 0.5)));
+    }
+
+    #[test]
+    fn dominated_rows_are_never_played() {
+        // The second row is always worse, the third column always better for
+        // the column player
+        let equilibrium = Equilibrium::solve(&[
+            vec![3.0, -1.0, -2.0],
+            vec![2.0, -2.0, -3.0],
+            vec![-1.0, 2.0, -2.0],
+        ]);
+        assert!(close(equilibrium.rows[1], 0.0));
+        assert!(close(equilibrium.value, -2.0));
+        assert!(close(equilibrium.cols[2], 1.0));
+
+        // Rock, paper, scissors
+        let equilibrium = Equilibrium::solve(&[
+            vec![0.0, -1.0, 1.0],
+            vec![1.0, 0.0, -1.0],
+            vec![-1.0, 1.0, 0.0],
+        ]);
+        assert!(close(equilibrium.value, 0.0));
+        assert!(equilibrium.rows.iter().all(|&p| close(p, 1.0 / 3.0)));
+    }
+}
@@ -0,0 +1,196 @@
+use std::slice::Iter;
+
+use lazy_static::lazy_static;
+
+use crate::game::MAX_PILLZ;
+
+static N: u8 = MAX_PILLZ + 1;
+lazy_static! {
+    #[derive(Debug)]
+    static ref SHIFT_RANGES: Vec<Vec<(u8, bool)>> = {
+        let mut ranges = Vec::with_capacity(N as usize);
+        for n in 0..N {
+            let mut range = Vec::new();
+
+            range.push((n, false));
+
+            if n < 3 {
+                for i in 0..n {
+                    range.push((i, false));
+                }
+            } else {
+                range.push((n - 3, true));
+                range.push((n - 3, false));
+
+                for i in 0..n - 3 {
+                    range.push((i, true));
+                    range.push((i, false));
+                }
+
+                range.push((n - 2, false));
+                range.push((n - 1, false));
+            }
+
+            ranges.push(range);
+        }
+
+        ranges
+    };
+    static ref SPLIT_SHIFT_RANGES: Vec<Vec<(u8, bool)>> = {
+        let mut ranges = Vec::with_capacity(N as usize);
+        for n in 0..N {
+            let mut range = Vec::new();
+
+            range.push((n, false));
+
+            if n < 3 {
+                for i in 0..n {
+                    range.push((i, false));
+                }
+            } else {
+                range.push((n - 3, false));
+
+                for i in 0..n - 3 {
+                    range.push((i, false));
+                }
+
+                range.push((n - 2, false));
+                range.push((n - 1, false));
+
+                range.push((n - 3, true));
+                for i in 0..n - 3 {
+                    range.push((i, true));
+                }
+            }
+
+            ranges.push(range);
+        }
+
+        ranges
+    };
+    static ref SHIFT_FALSE_RANGES: Vec<Vec<(u8, bool)>> = {
+        let mut ranges = Vec::with_capacity(N as usize);
+        for n in 0..N {
+            let mut range = Vec::new();
+
+            range.push((n, false));
+
+            if n < 3 {
+                for i in 0..n {
+                    range.push((i, false));
+                }
+            } else {
+                for i in 0..n - 2 {
+                    range.push((i, false));
+                }
+
+                range.push((n - 2, false));
+                range.push((n - 1, false));
+            }
+
+            ranges.push(range);
+        }
+
+        ranges
+    };
+    static ref RANGES: Vec<Vec<(u8, bool)>> = {
+        let mut ranges = Vec::with_capacity(N as usize);
+        for n in 0..N {
+            let mut range = Vec::new();
+
+            if n >= 3 {
+                for i in 0..n-2 {
+                    range.push((i, false));
+                    range.push((i, true));
+                }
+
+                for i in n-2..=n {
+                    range.push((i, false));
+                }
+            }
+
+            ranges.push(range);
+        }
+
+        ranges
+    };
+    static ref SPLIT_RANGES: Vec<Vec<(u8, bool)>> = {
+        let mut ranges = Vec::with_capacity(N as usize);
+        for n in 0..N {
+            let mut range = Vec::new();
+
+            for i in 0..=n {
+                range.push((i, false));
+            }
+
+            if n >= 3 {
+                for i in 0..=n-3 {
+                    range.push((i, true));
+                }
+            }
+
+            ranges.push(range);
+        }
+
+        ranges
+    };
+    static ref FALSE_RANGES: Vec<Vec<(u8, bool)>> = {
+        let mut ranges = Vec::with_capacity(N as usize);
+        for n in 0..N {
+            let mut range = Vec::new();
+
+            for i in 0..=n {
+                range.push((i, false));
+            }
+
+            ranges.push(range);
+        }
+
+        ranges
+    };
+}
+
+// #[inline]
+// fn shift_range(n: u8) -> Iter<'static, (u8, bool)> {
+//     SHIFT_RANGES[n as usize].iter()
+// }
+
+#[inline]
+pub(crate) fn split_shift_range(n: u8) -> Iter<'static, (u8, bool)> {
+    SPLIT_SHIFT_RANGES[n as usize].iter()
+}
+
+// #[inline]
+// fn range(n: u8) -> Iter<'static, (u8, bool)> {
+//     RANGES[n as usize].iter()
+// }
+
+#[inline]
+pub(crate) fn split_range(n: u8) -> Iter<'static, (u8, bool)> {
+    SPLIT_RANGES[n as usize].iter()
+}
+
+// #[inline]
+// fn false_range(n: u8, round: u8) -> Iter<'static, (u8, bool)> {
+//     if round == 0 {
+//         FALSE_RANGES[n as usize].iter()
+//     } else {
+//         SPLIT_RANGES[n as usize].iter()
+//     }
+// }
+
+#[inline]
+pub(crate) fn shift_false_range(n: u8, round: u8) -> Iter<'static, (u8, bool)> {
+    if round == 0 {
+        SHIFT_FALSE_RANGES[n as usize].iter()
+    } else {
+        SPLIT_SHIFT_RANGES[n as usize].iter()
+    }
+}
+
+#[test]
+fn test() {
+    for i in 0..N {
+        println!("{:?}", SPLIT_RANGES[i as usize]);
+    }
+}
@@ -0,0 +1,97 @@
+use std::{
+    array,
+    sync::{
+        atomic::{AtomicU32, Ordering},
+        Mutex,
+    },
+};
+
+use nohash_hasher::IntMap;
+
+use super::SelectionResult;
+
+/// How a stored result relates to the real one. Only alpha-beta stores
+/// results that aren't exact.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Bound {
+    Exact,
+    /// The real result is at least as good for the player.
+    Lower,
+    /// The real result is at most as good for the player.
+    Upper,
+}
+
+/// A stored result, the evaluation of the game it leads to and its bound.
+pub type Entry = (SelectionResult, f32, Bound);
+
+/// Locks the table is split over, so threads rarely wait on each other.
+const SHARDS: usize = 64;
+
+/// Results of positions already solved, keyed by `Game::position_hash`.
+///
+/// Shared by every thread of a search. The keys are already hashes, so the
+/// maps don't hash them again. The mixed strategies keep their `f64` values
+/// in a `Table<f64>`.
+pub struct Table<T = Entry> {
+    shards: [Mutex<IntMap<u64, T>>; SHARDS],
+    hits: AtomicU32,
+    misses: AtomicU32,
+}
+
+impl<T> Default for Table<T> {
+    fn default() -> Self {
+        Table {
+            shards: array::from_fn(|_| Mutex::default()),
+            hits: AtomicU32::new(0),
+            misses: AtomicU32::new(0),
+        }
+    }
+}
+
+impl<T: Copy> Table<T> {
+    #[inline]
+    fn shard(&self, key: u64) -> &Mutex<IntMap<u64, T>> {
+        &self.shards[key as usize % SHARDS]
+    }
+
+    /// The stored entry, counted as a hit or a miss.
+    pub fn probe(&self, key: u64) -> Option<T> {
+        let entry = self.shard(key).lock().unwrap().get(&key).copied();
+        let counter = if entry.is_some() {
+            &self.hits
+        } else {
+            &self.misses
+        };
+        counter.fetch_add(1, Ordering::Relaxed);
+        entry
+    }
+
+    pub fn put(&self, key: u64, entry: T) {
+        self.shard(key).lock().unwrap().insert(key, entry);
+    }
+
+    pub fn hits(&self) -> u32 {
+        self.hits.load(Ordering::Relaxed)
+    }
+
+    pub fn misses(&self) -> u32 {
+        self.misses.load(Ordering::Relaxed)
+    }
+}
+
+impl Table {
+    pub fn get(&self, key: u64) -> Option<SelectionResult> {
+        match self.probe(key) {
+            Some((result, _, Bound::Exact)) => Some(result),
+            _ => None,
+        }
+    }
+
+    pub fn insert(&self, key: u64, result: SelectionResult) {
+        self.store(key, result, 0.0, Bound::Exact);
+    }
+
+    pub fn store(&self, key: u64, result: SelectionResult, value: f32, bound: Bound) {
+        self.put(key, (result, value, bound));
+    }
+}
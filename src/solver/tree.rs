@@ -0,0 +1,223 @@
+use std::collections::HashMap;
+
+use super::{eval::Score, split_shift_range, table::Bound, Search, SelectionResult, Solver};
+use crate::game::{Game, GameStatus, PlayerType, Selection};
+
+/// Tree of results data structures
+#[derive(Debug)]
+pub(crate) enum ResultsTree {
+    /// A finished game, or a position already in the table.
+    End(Score),
+    /// The player choosing and the result of each of their selections.
+    Map(PlayerType, HashMap<Selection, ResultsTree>),
+}
+
+impl ResultsTree {
+    /// Who wins when both players make their best selections, and the
+    /// evaluation of the game.
+    fn score(&self) -> Score {
+        match self {
+            ResultsTree::End(score) => *score,
+            ResultsTree::Map(turn, map) => ResultsTree::best_move(*turn, map).1,
+        }
+    }
+
+    /// The best selection for `turn` and its score. Ties go to the lowest
+    /// card index and pillz.
+    fn best_move(turn: PlayerType, map: &HashMap<Selection, ResultsTree>) -> (Selection, Score) {
+        map.iter()
+            .map(|(&selection, tree)| (selection, tree.score()))
+            .min_by(|(a, score_a), (b, score_b)| {
+                let better = match turn {
+                    PlayerType::Player => score_b.cmp(score_a),
+                    PlayerType::Opponent => score_a.cmp(score_b),
+                };
+                better.then((a.index, a.pillz, a.fury).cmp(&(b.index, b.pillz, b.fury)))
+            })
+            .unwrap()
+    }
+}
+
+/// Builds the whole tree of selections before reading the result from it.
+/// A position reached again through other selections is read back from the
+/// table rather than built again.
+pub struct Tree;
+
+impl Tree {
+    /// The tree of a position, or just its result if the table already has
+    /// it from another order of the same selections.
+    fn subtree(game: &Game, search: &Search) -> ResultsTree {
+        let key = game.position_hash();
+        if let Some((result, eval, Bound::Exact)) = search.table.probe(key) {
+            return ResultsTree::End(Score::of(result, eval));
+        }
+        let turn = game.get_turn();
+        let map = Tree::fill_tree(game, search);
+        let (selection, score) = ResultsTree::best_move(turn, &map);
+        search
+            .table
+            .store(key, score.result(selection), score.eval, Bound::Exact);
+        ResultsTree::Map(turn, map)
+    }
+
+    /// Constructs a tree of results data structures
+    /// for all possible game states.
+    pub(crate) fn fill_tree(game: &Game, search: &Search) -> HashMap<Selection, ResultsTree> {
+        let mut result_tree = HashMap::new();
+
+        let pillz = game.get_turn_player().pillz;
+
+        // Select all possible selections for the current player.
+        for index in 0..4 {
+            if game.get_turn_hand()[index].played {
+                continue;
+            }
+
+            for &(pillz, fury) in split_shift_range(pillz) {
+                let mut game = game.clone();
+                if game.select(index, pillz, fury).is_some() {
+                    search.count_battle();
+                }
+
+                let selection = Selection { index, pillz, fury };
+                let tree = match game.status() {
+                    GameStatus::Playing => Tree::subtree(&game, search),
+                    _ => ResultsTree::End(Score::terminal(&game, search)),
+                };
+                result_tree.insert(selection, tree);
+            }
+        }
+        result_tree
+    }
+}
+
+impl Solver for Tree {
+    fn value(&self, game: &Game, search: &Search) -> (SelectionResult, f32) {
+        let key = game.position_hash();
+        if let Some((result, eval, Bound::Exact)) = search.table.probe(key) {
+            return (result, eval);
+        }
+        let tree = Tree::fill_tree(game, search);
+        let (selection, score) = ResultsTree::best_move(game.get_turn(), &tree);
+        let result = score.result(selection);
+        search.table.store(key, result, score.eval, Bound::Exact);
+        (result, score.eval)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::{split_shift_range, ResultsTree, Score, Tree};
+    use crate::{
+        card::Hand,
+        game::{Game, GameStatus, PlayerType, Selection},
+        output::Silent,
+        solver::{AlphaBeta, Search, SelectionResult, Solver},
+    };
+
+    fn game() -> Game {
+        let h1 = Hand::from_names("Genmaicha", "Orka", "Sando", "Deborah");
+        let h2 = Hand::from_names("Nathan", "El Kuzco", "Noon Steevens", "Strygia");
+        let mut game = Game::new(h1, h2);
+        game.output = &Silent;
+        game.select(1, 3, false); // Orka
+        game.select(3, 0, false); // Strygia
+        game.select(0, 4, false); // Nathan
+        game
+    }
+
+    /// Battles of the whole tree, built without the table.
+    fn battles(game: &Game) -> u32 {
+        let mut count = 0;
+        for index in (0..4).filter(|&i| !game.get_turn_hand()[i].played) {
+            for &(pillz, fury) in split_shift_range(game.get_turn_player().pillz) {
+                let mut g = *game;
+                if g.select(index, pillz, fury).is_some() {
+                    count += 1;
+                }
+                if g.status() == GameStatus::Playing {
+                    count += battles(&g);
+                }
+            }
+        }
+        count
+    }
+
+    fn end(result: SelectionResult, eval: f32) -> ResultsTree {
+        ResultsTree::End(Score::of(result, eval))
+    }
+
+    #[test]
+    fn best_move_ranks_results() {
+        let any = Selection::default();
+        let map = HashMap::from([
+            (
+                Selection::new(0, 0, false),
+                end(SelectionResult::Opponent(any), -2.0),
+            ),
+            (
+                Selection::new(1, 0, false),
+                end(SelectionResult::Draw(any), 0.0),
+            ),
+            (
+                Selection::new(2, 3, false),
+                end(SelectionResult::Player(any), 5.0),
+            ),
+            (
+                Selection::new(2, 1, false),
+                end(SelectionResult::Player(any), 5.0),
+            ),
+            (
+                Selection::new(3, 0, false),
+                end(SelectionResult::Player(any), 3.0),
+            ),
+        ]);
+        // The bigger win, then the lowest pillz
+        let (selection, score) = ResultsTree::best_move(PlayerType::Player, &map);
+        assert_eq!(selection, Selection::new(2, 1, false));
+        assert_eq!(score, Score::of(SelectionResult::Player(any), 5.0));
+        let (selection, _) = ResultsTree::best_move(PlayerType::Opponent, &map);
+        assert_eq!(selection, Selection::new(0, 0, false));
+
+        // The opponent picks in the subtree, so it keeps its win
+        let tree = ResultsTree::Map(PlayerType::Opponent, map);
+        let nested = HashMap::from([(Selection::new(3, 0, false), tree)]);
+        let score = ResultsTree::Map(PlayerType::Player, nested).score();
+        assert_eq!(score.result(any), SelectionResult::Opponent(any));
+    }
+
+    #[test]
+    fn solves_from_the_tree() {
+        let game = game();
+        let search = Search::default();
+        let tree = Tree::fill_tree(&game, &search);
+        assert!(search.battles() > 0);
+        let (selection, score) = ResultsTree::best_move(game.get_turn(), &tree);
+
+        let best = Tree.best(&game, &Search::default());
+        assert_eq!(best, score.result(selection));
+
+        // Alpha-beta may pick another selection with the same result
+        let alpha_beta = AlphaBeta.best(&game, &Search::default());
+        assert_eq!(
+            std::mem::discriminant(&alpha_beta),
+            std::mem::discriminant(&best)
+        );
+    }
+
+    #[test]
+    fn table_skips_transpositions() {
+        let game = game();
+        let search = Search::default();
+        Tree.best(&game, &search);
+        assert!(search.table.hits() > 0);
+        println!(
+            "{} battles, {} without the table",
+            search.battles(),
+            battles(&game)
+        );
+        assert!(search.battles() < battles(&game));
+    }
+}
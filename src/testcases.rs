@@ -3,9 +3,18 @@ mod testcases {
     use serde::Deserialize;
     use simd_json::from_reader;
 
-    use std::{fs::File, path::Path};
+    use std::{fs::File, mem::discriminant};
 
-    use crate::{card::Hand, game::Game};
+    use urban_recreation_rust::{
+        ability::Condition,
+        assets,
+        battle::{BattleEvent, BattleLog, StatusEffect, StatusKind, Statuses},
+        card::Hand,
+        game::{Game, GameStatus, PlayerType},
+        modifiers::{Keyword, Stat},
+        output::Silent,
+        solver::{AlphaBeta, Search, Solver, Tree},
+    };
 
     #[derive(Clone, Debug, Deserialize)]
     struct Move {
@@ -21,37 +30,46 @@ mod testcases {
     struct Testcase {
         cards: [String; 8],
         flip: bool,
+        #[serde(default)]
+        night: bool,
         life: u8,
         pillz: u8,
         moves: Vec<Move>,
     }
 
-    #[test]
-    fn testcases() {
-        let data_file = File::open(Path::new("./assets/testcases10000.json")).unwrap();
-        let json: Vec<Testcase> = from_reader(data_file).unwrap();
-
-        for (i, t) in json.into_iter().enumerate() {
+    impl Testcase {
+        fn game(&self) -> Game {
             let h1 = Hand::from_names(
-                t.cards[0].as_str(),
-                t.cards[1].as_str(),
-                t.cards[2].as_str(),
-                t.cards[3].as_str(),
+                self.cards[0].as_str(),
+                self.cards[1].as_str(),
+                self.cards[2].as_str(),
+                self.cards[3].as_str(),
             );
             let h2 = Hand::from_names(
-                t.cards[4].as_str(),
-                t.cards[5].as_str(),
-                t.cards[6].as_str(),
-                t.cards[7].as_str(),
+                self.cards[4].as_str(),
+                self.cards[5].as_str(),
+                self.cards[6].as_str(),
+                self.cards[7].as_str(),
             );
 
             let mut game = Game::new(h1, h2);
-            game.flip = t.flip as u8;
-            game.p1.life = t.life;
-            game.p2.life = t.life;
-            game.p1.pillz = t.pillz;
-            game.p2.pillz = t.pillz;
+            game.flip = self.flip as u8;
+            game.day = !self.night;
+            game.p1.start_with(self.life, self.pillz);
+            game.p2.start_with(self.life, self.pillz);
+            game
+        }
+    }
 
+    fn load() -> Vec<Testcase> {
+        let data_file = File::open(assets::path("testcases.json")).unwrap();
+        from_reader(data_file).unwrap()
+    }
+
+    #[test]
+    fn testcases() {
+        for (i, t) in load().into_iter().enumerate() {
+            let mut game = t.game();
             game.print_status();
 
             for Move {
@@ -91,4 +109,317 @@ mod testcases {
             }
         }
     }
+
+    /// Alpha-beta finds the same results as the full tree from every
+    /// testcase position from the third round on, the earlier ones being
+    /// too big for the tree, with fewer battles.
+    #[test]
+    fn alpha_beta_matches_tree() {
+        let (mut tree, mut alpha_beta) = (0, 0);
+        for (i, t) in load().into_iter().enumerate() {
+            let mut game = t.game();
+            game.output = &Silent;
+
+            for Move { s1, s2, .. } in t.moves {
+                game.select(s1.0, s1.1, s1.2);
+                game.select(s2.0, s2.1, s2.2);
+                if game.round < 2 || game.status() != GameStatus::Playing {
+                    continue;
+                }
+
+                // Also with the first selection made, hiding its pillz
+                let mut pending = game;
+                let index = (0..4).find(|&j| !pending.get_turn_hand()[j].played);
+                pending.select(index.unwrap(), 1, false);
+
+                for game in [game, pending] {
+                    let (search, search_ab) = (Search::default(), Search::default());
+                    let expected = Tree.solve(&game, &search);
+                    let result = AlphaBeta.solve(&game, &search_ab);
+                    assert_eq!(
+                        discriminant(&result),
+                        discriminant(&expected),
+                        "Testcase {}: {} should be {}",
+                        i,
+                        result,
+                        expected
+                    );
+                    tree += search.battles();
+                    alpha_beta += search_ab.battles();
+                }
+            }
+        }
+        println!(
+            "{} battles for the tree, {} for alpha-beta",
+            tree, alpha_beta
+        );
+        assert!(alpha_beta > 0 && alpha_beta < tree);
+    }
+
+    #[test]
+    fn battle_log() {
+        let h1 = Hand::from_names("Figaro", "Elvis", "Danae", "Vivian");
+        let h2 = Hand::from_names("Cell", "John O Clock", "Hollow Spyke", "Dr Falkenstein");
+
+        let mut game = Game::new(h1, h2);
+        game.day = false;
+
+        assert!(game.select(0, 0, false).is_none());
+        let log = game.select(0, 0, false).unwrap();
+
+        let entries = log.entries();
+        assert!(entries.contains(&BattleEvent::ConditionMet {
+            player: PlayerType::Player,
+            condition: Condition::Night,
+        }));
+        assert!(entries.contains(&BattleEvent::ModifierApplied {
+            player: PlayerType::Player,
+            stat: Stat::Power,
+            opp: false,
+            before: 7,
+            after: 8,
+        }));
+        assert_eq!(
+            entries[entries.len() - 2..],
+            [
+                BattleEvent::RoundWon {
+                    player: PlayerType::Player
+                },
+                BattleEvent::Damage {
+                    player: PlayerType::Opponent,
+                    amount: 5
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn silent_battle() {
+        let h1 = Hand::from_names("Figaro", "Elvis", "Danae", "Vivian");
+        let h2 = Hand::from_names("Cell", "John O Clock", "Hollow Spyke", "Dr Falkenstein");
+
+        let mut game = Game::new(h1, h2);
+        game.output = &Silent;
+
+        game.select(0, 0, false);
+        let log = game.select(0, 0, false).unwrap();
+
+        assert!(log.entries().is_empty());
+        assert_eq!(game.p2.life, 8);
+    }
+
+    #[test]
+    fn corrosion() {
+        let h1 = Hand::from_names("Cameron", "Elvis", "Danae", "Vivian");
+        let h2 = Hand::from_names("Cell", "John O Clock", "Hollow Spyke", "Dr Falkenstein");
+
+        let mut game = Game::new(h1, h2);
+
+        game.select(0, 0, false);
+        let log = game.select(0, 0, false).unwrap();
+        assert!(log.entries().contains(&BattleEvent::StatusAdded {
+            player: PlayerType::Player,
+            kind: StatusKind::Corrosion,
+            opp: true,
+        }));
+        assert_eq!(game.p1.statuses.iter().count(), 0);
+
+        // 1 life at the end of round 2, 2 at the end of round 3
+        for loss in [1, 2] {
+            game.select(loss, 0, false);
+            let log = game.select(loss, 0, false).unwrap();
+            let ticked = log.iter().find_map(|e| match *e {
+                BattleEvent::StatusTicked {
+                    player: PlayerType::Opponent,
+                    kind: StatusKind::Corrosion,
+                    before,
+                    after,
+                } => Some((before, after)),
+                _ => None,
+            });
+            let (before, after) = ticked.unwrap();
+            assert_eq!(before - after, (loss as u8).min(before));
+            assert_eq!(game.p2.life, after);
+        }
+    }
+
+    #[test]
+    fn status_replacement() {
+        let mut game = Game::new(
+            Hand::from_names("Figaro", "Elvis", "Danae", "Vivian"),
+            Hand::from_names("Cell", "John O Clock", "Hollow Spyke", "Dr Falkenstein"),
+        );
+        let corrosion = |amount, limit| StatusEffect {
+            kind: StatusKind::Corrosion,
+            amount,
+            limit,
+            ticks: 0,
+        };
+
+        game.p1.statuses.add(corrosion(2, 0));
+        Statuses::tick(&mut game.p1, &mut Default::default());
+        assert_eq!(game.p1.life, 10);
+
+        // A new Corrosion replaces the old one and starts over
+        game.p1.statuses.add(corrosion(1, 8));
+        assert_eq!(game.p1.statuses.iter().count(), 1);
+        Statuses::tick(&mut game.p1, &mut Default::default());
+        assert_eq!(game.p1.life, 9);
+        Statuses::tick(&mut game.p1, &mut Default::default());
+        assert_eq!(game.p1.life, 8);
+        Statuses::tick(&mut game.p1, &mut Default::default());
+        assert_eq!(game.p1.life, 8);
+    }
+
+    #[test]
+    fn cancel_leader() {
+        let h1 = Hand::from_names("Hugo", "Elvis", "Danae", "Vivian");
+        // Two leaders, so the Cancel Leader bonus is active
+        let h2 = Hand::from_names("Mr Big Duke", "Ambre", "Hollow Spyke", "Cell");
+        let mut game = Game::new(h1, h2);
+        let hugo_attack = |log: &BattleLog| {
+            log.iter().any(|e| {
+                matches!(
+                    e,
+                    BattleEvent::ModifierApplied {
+                        player: PlayerType::Player,
+                        stat: Stat::Attack,
+                        ..
+                    }
+                )
+            })
+        };
+
+        // Mr Big Duke's bonus cancels Hugo's +7 Attack for the round
+        game.select(1, 0, false);
+        let log = game.select(0, 0, false).unwrap();
+        assert!(log.entries().contains(&BattleEvent::LeaderCancelled {
+            player: PlayerType::Opponent,
+            opp: true,
+        }));
+        assert!(!hugo_attack(&log));
+
+        // The opponent picks first in the second round
+        game.select(2, 0, false);
+        let log = game.select(2, 0, false).unwrap();
+        assert!(hugo_attack(&log));
+    }
+
+    #[test]
+    fn tune_out() {
+        let h1 = Hand::from_names("Noon Steevens", "Elvis", "Danae", "Vivian");
+        let h2 = Hand::from_names("Blaaster Cr", "John O Clock", "Hollow Spyke", "Cell");
+        let mut game = Game::new(h1, h2);
+
+        // Blaaster's -2 Opp Power can't touch Noon Steevens
+        game.select(0, 0, false);
+        let log = game.select(0, 0, false).unwrap();
+        assert!(log.entries().contains(&BattleEvent::TunedOut {
+            player: PlayerType::Player,
+            both: false,
+        }));
+        assert!(log.entries().contains(&BattleEvent::ModifierBlocked {
+            player: PlayerType::Opponent,
+            stat: Stat::Power,
+            opp: true,
+        }));
+    }
+
+    #[test]
+    fn tie_break() {
+        let h1 = Hand::from_names("Solomon", "Elvis", "Danae", "Vivian");
+        let h2 = Hand::from_names("Bazalt", "John O Clock", "Hollow Spyke", "Cell");
+        let mut game = Game::new(h1, h2);
+
+        // 8 attack each, Bazalt's lower level would win the tie
+        game.select(0, 0, false);
+        let log = game.select(0, 0, false).unwrap();
+        assert!(log.entries().contains(&BattleEvent::Keyword {
+            player: PlayerType::Player,
+            keyword: Keyword::TieBreak,
+        }));
+        assert!(log.entries().contains(&BattleEvent::RoundWon {
+            player: PlayerType::Player,
+        }));
+    }
+
+    /// Whether `player` had an attack modifier applied.
+    fn attack_modified(log: &BattleLog, player: PlayerType) -> bool {
+        log.iter().any(|e| {
+            matches!(
+                e,
+                BattleEvent::ModifierApplied { player: p, stat: Stat::Attack, .. } if *p == player
+            )
+        })
+    }
+
+    #[test]
+    fn stopped_leader() {
+        let h1 = Hand::from_names("Hugo", "Danae", "Vivian", "Cell");
+        let h2 = Hand::from_names("Elvis", "John O Clock", "Hollow Spyke", "Bazalt");
+        let mut game = Game::new(h1, h2);
+
+        // Elvis stops the ability of Hugo, and with it Hugo's +7 Attack
+        game.select(0, 0, false);
+        let log = game.select(0, 0, false).unwrap();
+        assert!(!attack_modified(&log, PlayerType::Player));
+
+        // Any other card still gets it
+        game.select(1, 0, false);
+        let log = game.select(1, 0, false).unwrap();
+        assert!(attack_modified(&log, PlayerType::Player));
+    }
+
+    #[test]
+    fn copied_leader() {
+        let h1 = Hand::from_names("Hugo", "Danae", "Vivian", "Cell");
+        let h2 = Hand::from_names("Wale", "John O Clock", "Hollow Spyke", "Bazalt");
+        let mut game = Game::new(h1, h2);
+
+        // Wale copies Hugo's +7 Attack for the round
+        game.select(0, 0, false);
+        let log = game.select(0, 0, false).unwrap();
+        assert!(attack_modified(&log, PlayerType::Player));
+        assert!(attack_modified(&log, PlayerType::Opponent));
+
+        game.select(1, 0, false);
+        let log = game.select(1, 0, false).unwrap();
+        assert!(!attack_modified(&log, PlayerType::Opponent));
+    }
+
+    #[test]
+    fn per_round_leader() {
+        let h1 = Hand::from_names("Morphun", "Danae", "Vivian", "Cell");
+        let h2 = Hand::from_names("Bazalt", "John O Clock", "Hollow Spyke", "Dr Falkenstein");
+        let mut game = Game::new(h1, h2);
+
+        // +1 Pillz at the end of every round
+        game.select(1, 0, false);
+        game.select(1, 0, false);
+        assert_eq!(game.p1.pillz, 13);
+
+        // The opponent picks first in the second round
+        game.select(2, 0, false);
+        game.select(2, 3, false);
+        assert_eq!(game.p1.pillz, 11);
+    }
+
+    #[test]
+    fn extreme_attack() {
+        let h1 = Hand::from_names("Numar", "Dave", "Hekate", "Danae");
+        let h2 = Hand::from_names("Bazalt", "John O Clock", "Hollow Spyke", "Dr Falkenstein");
+        let mut game = Game::new(h1, h2);
+        game.p1.pillz = 16;
+
+        // 13 pillz and fury, doubled by Overdose, on 8 power +2
+        game.select(0, 13, true);
+        let log = game.select(0, 0, false).unwrap();
+        assert!(log.entries().contains(&BattleEvent::Attack {
+            player: PlayerType::Player,
+            attack: 270,
+        }));
+        assert!(log.entries().contains(&BattleEvent::RoundWon {
+            player: PlayerType::Player,
+        }));
+    }
 }
@@ -2,11 +2,12 @@ use std::{marker::PhantomData, mem::swap};
 
 use serde::{
     de::{SeqAccess, Visitor},
-    Deserialize, Deserializer,
+    ser::SerializeSeq,
+    Deserialize, Deserializer, Serialize, Serializer,
 };
 use tinyvec::Array;
 
-#[derive(Debug, Clone, Copy, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq, Hash)]
 pub struct StackVec4<A> {
     pub len: usize,
     pub data: [Option<A>; 4],
@@ -71,6 +72,23 @@ impl<A> StackVec4<A> {
     }
 }
 
+impl<A> Serialize for StackVec4<A>
+where
+    A: Serialize,
+{
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let values = self.data[..self.len].iter().flatten();
+        let mut seq = serializer.serialize_seq(Some(values.clone().count()))?;
+        for value in values {
+            seq.serialize_element(value)?;
+        }
+        seq.end()
+    }
+}
+
 impl<'de, A> Deserialize<'de> for StackVec4<A>
 where
     A: Deserialize<'de>,
@@ -111,6 +129,34 @@ where
     }
 }
 
+/// Narrowing from the `i32` stat arithmetic is done in, clamping to the
+/// range of the type instead of wrapping.
+pub trait Clamp: Sized {
+    fn clamp_from(value: i32) -> Self;
+}
+
+impl Clamp for u8 {
+    #[inline]
+    fn clamp_from(value: i32) -> Self {
+        value.clamp(0, u8::MAX as i32) as u8
+    }
+}
+
+impl Clamp for u16 {
+    #[inline]
+    fn clamp_from(value: i32) -> Self {
+        value.clamp(0, u16::MAX as i32) as u16
+    }
+}
+
+#[test]
+fn test_clamp() {
+    assert_eq!(u8::clamp_from(-7), 0);
+    assert_eq!(u8::clamp_from(300), 255);
+    assert_eq!(u16::clamp_from(300), 300);
+    assert_eq!(u16::clamp_from(i32::MAX), u16::MAX);
+}
+
 #[test]
 fn test_stackvec4() {
     let mut vec = StackVec4::<u8>::default();